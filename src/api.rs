@@ -1,10 +1,23 @@
-use reqwest::Url;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::WrapErr;
+use futures::stream::{self, Stream, StreamExt};
+use reqwest::{StatusCode, Url};
 use serde::de::Error as _;
 use serde::{de::DeserializeOwned, Deserialize, Deserializer};
 use uuid::Uuid;
 
+use crate::auth;
+use crate::cache::Cache;
 use crate::domain::DeviceName;
+use crate::metadata::SimulationMeta;
 use crate::simulation;
+use crate::sse;
+use crate::tls;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct SimulationOverview {
@@ -21,6 +34,41 @@ where
     jiff::Timestamp::from_millisecond(n).map_err(D::Error::custom)
 }
 
+/// One page of the simulation list.
+#[derive(Debug, Clone)]
+pub struct SimulationPage {
+    pub simulations: Vec<SimulationOverview>,
+    /// Cursor of the next page, `None` on the last one.
+    pub next: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PageResponse {
+    Paged {
+        items: Vec<SimulationOverview>,
+        #[serde(default)]
+        next: Option<String>,
+    },
+    /// Servers without paging ignore the parameters and send the whole list.
+    All(Vec<SimulationOverview>),
+}
+
+impl From<PageResponse> for SimulationPage {
+    fn from(res: PageResponse) -> Self {
+        match res {
+            PageResponse::Paged { items, next } => Self {
+                simulations: items,
+                next,
+            },
+            PageResponse::All(simulations) => Self {
+                simulations,
+                next: None,
+            },
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone, Copy)]
 pub struct Report {
     pub result: i64,
@@ -28,6 +76,8 @@ pub struct Report {
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Simulation {
+    /// Names as the server reports them, which may not pass [`DeviceName::try_new`].
+    #[serde(deserialize_with = "DeviceName::deserialize_all_from_server")]
     pub devices: Vec<DeviceName>,
     pub res: Report,
 }
@@ -38,77 +88,913 @@ pub struct SubmittedSimulation {
     pub report: Report,
 }
 
+/// Progress of a running simulation.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationStatus {
+    pub state: RunState,
+    /// Ticks completed so far.
+    #[serde(default)]
+    pub tick: u64,
+    #[serde(default)]
+    pub total_ticks: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RunState {
+    Queued,
+    Running,
+    Finished,
+    Failed,
+}
+
+/// One line a device wrote to its log.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LogLine {
+    #[serde(deserialize_with = "DeviceName::deserialize_from_server")]
+    pub device: DeviceName,
+    pub line: String,
+}
+
+/// Something that happened to a running simulation.
+#[derive(Debug, Clone)]
+pub enum SimulationEvent {
+    Status(SimulationStatus),
+    Log(LogLine),
+    Finished(Report),
+    /// The simulation failed, with the server's explanation.
+    Failed(String),
+}
+
+impl SimulationEvent {
+    /// Interprets a server-sent event, `None` for event types we don't know.
+    fn from_sse(event: &sse::Event) -> Option<serde_json::Result<Self>> {
+        Some(match event.event.as_str() {
+            "status" => serde_json::from_str(&event.data).map(Self::Status),
+            "log" => serde_json::from_str(&event.data).map(Self::Log),
+            "finished" => serde_json::from_str(&event.data).map(Self::Finished),
+            "failed" => Ok(Self::Failed(event.data.clone())),
+            _ => return None,
+        })
+    }
+}
+
+/// Events of one simulation as the server sends them.
+pub struct EventStream {
+    response: reqwest::Response,
+    parser: sse::Parser,
+    pending: VecDeque<sse::Event>,
+}
+
+impl EventStream {
+    /// The next event, `None` once the server closed the stream.
+    pub async fn next(&mut self) -> Option<eyre::Result<SimulationEvent>> {
+        loop {
+            while let Some(event) = self.pending.pop_front() {
+                if let Some(res) = SimulationEvent::from_sse(&event) {
+                    return Some(res.wrap_err_with(|| format!("Invalid {} event", event.event)));
+                }
+            }
+            match self.response.chunk().await {
+                Ok(Some(chunk)) => self.pending.extend(self.parser.push(&chunk)),
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e).wrap_err("Event stream interrupted")),
+            }
+        }
+    }
+}
+
+/// Why a request to the server failed.
+#[derive(Debug)]
+pub enum Error {
+    /// The server couldn't be reached or the connection broke.
+    Connect(reqwest::Error),
+    /// The server didn't answer in time.
+    Timeout(reqwest::Error),
+    /// The server answered with an error status.
+    Status {
+        code: StatusCode,
+        body: String,
+        /// The body parsed as RFC 9457 problem details, if it is some.
+        problem: Option<Problem>,
+    },
+    /// The response wasn't what we expected.
+    Decode {
+        message: String,
+        /// The start of the response body.
+        snippet: String,
+    },
+    /// The request URL couldn't be built from the endpoint.
+    InvalidUrl(String),
+    /// The server rejected the access token and getting a new one failed.
+    Refresh(String),
+}
+
+/// How much of a response body is kept for error messages.
+const SNIPPET_LEN: usize = 200;
+
+impl Error {
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Status { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    pub fn problem(&self) -> Option<&Problem> {
+        match self {
+            Error::Status { problem, .. } => problem.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn decode(e: impl fmt::Display, body: &str) -> Self {
+        Error::Decode {
+            message: e.to_string(),
+            snippet: snippet(body).to_string(),
+        }
+    }
+}
+
+/// The start of `body`, cut at a character boundary.
+fn snippet(body: &str) -> &str {
+    let body = body.trim();
+    match body.char_indices().nth(SNIPPET_LEN) {
+        Some((end, _)) => &body[..end],
+        None => body,
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Connect(e) => write!(f, "Can't reach the server: {e}"),
+            Error::Timeout(_) => write!(f, "The server didn't answer in time"),
+            Error::Status {
+                code,
+                body,
+                problem,
+            } => {
+                write!(f, "Server responded with {code}")?;
+                match problem.as_ref().and_then(Problem::summary) {
+                    Some(summary) => write!(f, ": {summary}"),
+                    None if !body.trim().is_empty() => write!(f, ": {}", snippet(body)),
+                    None => Ok(()),
+                }
+            }
+            Error::Decode { message, snippet } => {
+                write!(f, "Unexpected response from the server ({message})")?;
+                if !snippet.is_empty() {
+                    write!(f, ": {snippet}")?;
+                }
+                Ok(())
+            }
+            Error::InvalidUrl(e) => write!(f, "Invalid server URL: {e}"),
+            Error::Refresh(e) => write!(f, "The session expired, log in again ({e})"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Connect(e) | Error::Timeout(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Error::Timeout(e)
+        } else if e.is_builder() {
+            Error::InvalidUrl(e.to_string())
+        } else if e.is_decode() {
+            Error::decode(e, "")
+        } else {
+            Error::Connect(e)
+        }
+    }
+}
+
+/// Problem details as described by RFC 9457, sent by servers along error statuses.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Problem {
+    pub title: Option<String>,
+    pub detail: Option<String>,
+    /// Validation errors, an extension many servers use.
+    #[serde(default)]
+    pub errors: Vec<FieldError>,
+}
+
+/// A problem with one part of the request.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Where the problem is, usually a JSON pointer like `/devices/0/name`.
+    #[serde(alias = "pointer", alias = "field", alias = "path")]
+    pub location: Option<String>,
+    #[serde(alias = "message")]
+    pub detail: String,
+}
+
+impl Problem {
+    /// Parses `body` if it is a problem with something to say.
+    fn parse(body: &str) -> Option<Self> {
+        let problem = serde_json::from_str::<Self>(body).ok()?;
+        (problem.summary().is_some() || !problem.errors.is_empty()).then_some(problem)
+    }
+
+    /// The detail if there is one, the title otherwise.
+    pub fn summary(&self) -> Option<&str> {
+        self.detail.as_deref().or(self.title.as_deref())
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{location}: {}", self.detail),
+            None => f.write_str(&self.detail),
+        }
+    }
+}
+
+/// Turns error statuses into [`Error::Status`], keeping the body.
+async fn check_status(res: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let code = res.status();
+    if !code.is_client_error() && !code.is_server_error() {
+        return Ok(res);
+    }
+    let body = res.text().await.unwrap_or_default();
+    Err(Error::Status {
+        code,
+        problem: Problem::parse(&body),
+        body,
+    })
+}
+
+/// Reads a JSON response, keeping the start of the body if it doesn't fit `T`.
+async fn read_json<T: DeserializeOwned>(res: reqwest::Response) -> Result<T, Error> {
+    let body = check_status(res).await?.text().await?;
+    decode_json(&body)
+}
+
+fn decode_json<T: DeserializeOwned>(body: &str) -> Result<T, Error> {
+    serde_json::from_str(body).map_err(|e| Error::decode(e, body))
+}
+
+/// Timeouts and retries of requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestPolicy {
+    pub connect_timeout: Duration,
+    /// Limit for whole requests, except event streams which last as long as a simulation.
+    pub timeout: Duration,
+    /// How often failed GET requests are retried.
+    pub retries: u32,
+    /// Whether submissions carry an idempotency key, which makes retrying them safe.
+    pub idempotent_submit: bool,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+            retries: 3,
+            idempotent_submit: false,
+        }
+    }
+}
+
+/// Wait before the first retry, doubled for every further one.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+/// Longest wait between retries, also for waits the server asks for.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How many device logs are fetched at once.
+pub const LOG_CONCURRENCY: usize = 4;
+
+/// How long to wait before retry number `attempt`, counting from 0.
+fn backoff(attempt: u32) -> Duration {
+    RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Parses a `Retry-After` header, either in seconds or as an HTTP date.
+fn retry_after(value: &str, now: jiff::Timestamp) -> Option<Duration> {
+    let value = value.trim();
+    let wait = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let at = jiff::fmt::rfc2822::parse(value).ok()?.timestamp();
+            // dates in the past mean retrying right away
+            at.duration_since(now).try_into().unwrap_or_default()
+        }
+    };
+    Some(wait.min(MAX_RETRY_DELAY))
+}
+
+/// Whether a response with `status` may succeed when tried again.
+fn retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// `base` with `segments` appended to its path, each percent-encoded as needed.
+///
+/// A base path like `/api/` is kept, so servers can live below the root.
+fn with_path(base: &Url, segments: &[&str]) -> Url {
+    let mut url = base.clone();
+    // fails only for URLs like `mailto:`, which `Client::new` rejects
+    if let Ok(mut path) = url.path_segments_mut() {
+        path.pop_if_empty().extend(segments);
+    }
+    url
+}
+
 pub struct Client {
     pub endpoint: Url,
     pub client: reqwest::Client,
+    pub policy: RequestPolicy,
+    /// Credentials sent along every request, shared with clients replacing this one.
+    pub auth: Arc<auth::Session>,
+    /// Responses kept across requests, shared with clients replacing this one.
+    pub cache: Arc<Cache>,
+    /// Whether the last request failed to reach the server.
+    offline: AtomicBool,
 }
 
 impl Client {
-    fn with_path(&self, path: &str) -> Url {
-        let mut ret = self.endpoint.clone();
-        ret.set_path(path);
-        ret
-    }
-
-    async fn get_json<T>(&self, path: &str) -> Result<T, reqwest::Error>
-    where
-        T: DeserializeOwned,
-    {
-        self.client
-            .get(self.with_path(path))
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await
+    pub fn new(
+        endpoint: Url,
+        policy: RequestPolicy,
+        auth: Arc<auth::Session>,
+        tls: &tls::TlsConfig,
+        cache: Arc<Cache>,
+    ) -> Result<Self, Error> {
+        if endpoint.cannot_be_a_base() {
+            return Err(Error::InvalidUrl(format!("{endpoint} can't have a path")));
+        }
+        let builder = reqwest::Client::builder().connect_timeout(policy.connect_timeout);
+        let client = tls.apply(builder).build()?;
+        Ok(Self {
+            endpoint,
+            client,
+            policy,
+            auth,
+            cache,
+            offline: AtomicBool::new(false),
+        })
     }
 
-    async fn get_string(&self, path: &str) -> Result<String, reqwest::Error> {
-        self.client
-            .get(self.with_path(path))
-            .send()
-            .await?
-            .error_for_status()?
-            .text()
-            .await
+    /// Whether the server couldn't be reached the last time, until a request gets through.
+    pub fn offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
     }
 
-    pub async fn fetch_all_simulations(&self) -> Result<Vec<SimulationOverview>, reqwest::Error> {
-        self.get_json("/simulation").await
+    /// Carries the state over from the client this one replaces.
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed);
+    }
+
+    /// Sends the request built by `request`, retrying as the policy allows if `retry`.
+    ///
+    /// Only idempotent requests may be retried, the first attempt may have reached the
+    /// server even if its response didn't reach us. Requests rejected as unauthorized are
+    /// always sent again once after refreshing the access token.
+    async fn send(
+        &self,
+        request: impl Fn() -> reqwest::RequestBuilder,
+        retry: bool,
+    ) -> Result<reqwest::Response, Error> {
+        let mut attempt = 0;
+        let mut refreshed = false;
+        loop {
+            let token = self.auth.access_token();
+            let res = self.auth.authorize(request()).send().await;
+            if !refreshed && matches!(&res, Ok(res) if res.status() == StatusCode::UNAUTHORIZED) {
+                refreshed = true;
+                match self.auth.refresh(&self.client, token.as_deref()).await {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => {
+                        // the server answered, only the token endpoint may not have
+                        self.set_offline(false);
+                        return Err(Error::Refresh(format!("{e:#}")));
+                    }
+                }
+            }
+            let wait = match &res {
+                Ok(res) if retryable(res.status()) => Some(
+                    res.headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| retry_after(value, jiff::Timestamp::now()))
+                        .unwrap_or_else(|| backoff(attempt)),
+                ),
+                Err(e) if e.is_connect() || e.is_timeout() => Some(backoff(attempt)),
+                _ => None,
+            };
+            match wait {
+                Some(wait) if retry && attempt < self.policy.retries => {
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                _ => {
+                    // any response, even an error, shows the server is reachable
+                    let unreachable = matches!(&res, Err(e) if e.is_connect() || e.is_timeout());
+                    self.set_offline(unreachable);
+                    return Ok(res?);
+                }
+            }
+        }
+    }
+
+    /// A GET request to `url`, limited by the request timeout.
+    fn get(&self, url: &Url) -> reqwest::RequestBuilder {
+        self.client.get(url.clone()).timeout(self.policy.timeout)
+    }
+
+    fn with_path(&self, segments: &[&str]) -> Url {
+        with_path(&self.endpoint, segments)
+    }
+
+    /// GETs a resource which never changes, from the cache if it was fetched before.
+    ///
+    /// Responses are only cached if `parse` accepts them.
+    async fn get_cached<T>(
+        &self,
+        segments: &[&str],
+        parse: impl Fn(&str) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let url = self.with_path(segments);
+        if let Some(value) = self
+            .cache
+            .get(url.as_str())
+            .and_then(|body| parse(&body).ok())
+        {
+            return Ok(value);
+        }
+        let res = self.send(|| self.get(&url), true).await?;
+        let body = check_status(res).await?.text().await?;
+        let value = parse(&body)?;
+        self.cache.insert(url.as_str(), body.into());
+        Ok(value)
     }
 
-    pub async fn fetch_simulation(&self, id: Uuid) -> Result<Simulation, reqwest::Error> {
-        self.get_json(&format!("/simulation/{id}")).await
+    /// GETs `url`, sending the ETag of the cached body so the server can tell us it's
+    /// still current instead of sending it again.
+    ///
+    /// Bodies are cached even without an ETag, to be shown while offline.
+    async fn get_revalidated(&self, url: &Url) -> Result<Arc<str>, Error> {
+        let cached = self.cache.tagged(url.as_str());
+        let request = || {
+            let request = self.get(url);
+            match cached.as_ref().and_then(|(etag, _)| etag.as_ref()) {
+                Some(etag) => request.header(reqwest::header::IF_NONE_MATCH, etag.as_str()),
+                None => request,
+            }
+        };
+        let res = self.send(request, true).await?;
+        if let (StatusCode::NOT_MODIFIED, Some((_, body))) = (res.status(), &cached) {
+            return Ok(body.clone());
+        }
+        let res = check_status(res).await?;
+        let etag = res
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(ToString::to_string);
+        let body: Arc<str> = res.text().await?.into();
+        self.cache.insert_tagged(url.as_str(), etag, body.clone());
+        Ok(body)
     }
 
-    pub async fn fetch_simulation_source(&self, id: Uuid) -> Result<String, reqwest::Error> {
-        self.get_string(&format!("/simulation/{id}/source")).await
+    /// GETs a log of simulation `id`, which only stays the same once it finished.
+    async fn get_log(&self, id: Uuid, segments: &[&str]) -> Result<String, Error> {
+        // the report is cached once fetched, which is only possible when finished
+        let report = self.with_path(&["simulation", &id.to_string()]);
+        if self.cache.contains(report.as_str()) {
+            self.get_cached(segments, |body| Ok(body.to_string())).await
+        } else {
+            self.get_string(segments).await
+        }
     }
 
-    pub async fn fetch_simulation_log(&self, id: Uuid) -> Result<String, reqwest::Error> {
-        self.get_string(&format!("/simulation/{id}/log")).await
+    async fn get_string(&self, segments: &[&str]) -> Result<String, Error> {
+        let url = self.with_path(segments);
+        let res = self.send(|| self.get(&url), true).await?;
+        Ok(check_status(res).await?.text().await?)
+    }
+
+    pub async fn fetch_all_simulations(&self) -> Result<Vec<SimulationOverview>, Error> {
+        let url = self.with_path(&["simulation"]);
+        decode_json(&self.get_revalidated(&url).await?)
+    }
+
+    /// The URL of the page of up to `limit` simulations starting at `cursor`.
+    fn page_url(&self, cursor: Option<&str>, limit: usize) -> Url {
+        let mut url = self.with_path(&["simulation"]);
+        url.query_pairs_mut()
+            .append_pair("limit", &limit.to_string());
+        if let Some(cursor) = cursor {
+            url.query_pairs_mut().append_pair("cursor", cursor);
+        }
+        url
+    }
+
+    /// The first page of simulations as last received, to browse while offline.
+    ///
+    /// Later pages can't be loaded offline, so the page is never followed by another.
+    pub fn cached_simulations_page(&self, limit: usize) -> Option<SimulationPage> {
+        let cached = |url: Url| self.cache.tagged(url.as_str()).map(|(_, body)| body);
+        let simulations = match cached(self.page_url(None, limit))
+            .and_then(|body| decode_json::<PageResponse>(&body).ok())
+        {
+            Some(page) => SimulationPage::from(page).simulations,
+            // servers without paging
+            None => decode_json(&cached(self.with_path(&["simulation"]))?).ok()?,
+        };
+        Some(SimulationPage {
+            simulations,
+            next: None,
+        })
+    }
+
+    /// Fetches up to `limit` simulations, starting at `cursor` or with the newest.
+    ///
+    /// Servers which don't support paging send the whole list as a single page.
+    pub async fn fetch_simulations_page(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<SimulationPage, Error> {
+        let url = self.page_url(cursor, limit);
+        let body = match self.get_revalidated(&url).await {
+            // some servers reject parameters they don't know
+            Err(e) if e.status() == Some(StatusCode::BAD_REQUEST) => {
+                return Ok(SimulationPage {
+                    simulations: self.fetch_all_simulations().await?,
+                    next: None,
+                });
+            }
+            res => res?,
+        };
+        decode_json::<PageResponse>(&body).map(Into::into)
+    }
+
+    pub async fn fetch_simulation(&self, id: Uuid) -> Result<Simulation, Error> {
+        // only finished simulations have a report
+        self.get_cached(&["simulation", &id.to_string()], decode_json)
+            .await
+    }
+
+    pub async fn fetch_simulation_source(&self, id: Uuid) -> Result<String, Error> {
+        self.get_cached(&["simulation", &id.to_string(), "source"], |body| {
+            Ok(body.to_string())
+        })
+        .await
+    }
+
+    pub async fn fetch_simulation_log(&self, id: Uuid) -> Result<String, Error> {
+        self.get_log(id, &["simulation", &id.to_string(), "log"])
+            .await
     }
 
     pub async fn fetch_simulation_log_by_device(
         &self,
         id: Uuid,
         device_name: &DeviceName,
-    ) -> Result<String, reqwest::Error> {
-        self.get_string(&format!("/simulation/{id}/log/{}", device_name.as_str()))
-            .await
+    ) -> Result<String, Error> {
+        self.get_log(
+            id,
+            &["simulation", &id.to_string(), "log", device_name.as_str()],
+        )
+        .await
+    }
+
+    /// Fetches the logs of `devices`, at most [`LOG_CONCURRENCY`] at a time.
+    ///
+    /// Each log comes with its device as soon as it's done, in no particular order, and
+    /// a device failing doesn't stop the others.
+    pub fn fetch_all_device_logs<'a>(
+        &'a self,
+        id: Uuid,
+        devices: &'a [DeviceName],
+    ) -> impl Stream<Item = (DeviceName, Result<String, Error>)> + 'a {
+        stream::iter(devices)
+            .map(move |device| async move {
+                let log = self.fetch_simulation_log_by_device(id, device).await;
+                (device.clone(), log)
+            })
+            .buffer_unordered(LOG_CONCURRENCY)
+    }
+
+    /// Stores the metadata of a simulation, only supported by some servers.
+    pub async fn update_metadata(&self, id: Uuid, meta: &SimulationMeta) -> Result<(), Error> {
+        let url = self.with_path(&["simulation", &id.to_string(), "metadata"]);
+        let request = || {
+            self.client
+                .put(url.clone())
+                .timeout(self.policy.timeout)
+                .json(meta)
+        };
+        check_status(self.send(request, false).await?).await?;
+        Ok(())
+    }
+
+    /// Deletes simulation `id` with its logs.
+    pub async fn delete_simulation(&self, id: Uuid) -> Result<(), Error> {
+        let url = self.with_path(&["simulation", &id.to_string()]);
+        let request = || self.client.delete(url.clone()).timeout(self.policy.timeout);
+        check_status(self.send(request, false).await?).await?;
+        Ok(())
+    }
+
+    /// Subscribes to status changes and log lines of simulation `id` while it runs.
+    pub async fn simulation_events(&self, id: Uuid) -> Result<EventStream, Error> {
+        let url = self.with_path(&["simulation", &id.to_string(), "events"]);
+        let request = || {
+            self.client
+                .get(url.clone())
+                .header(reqwest::header::ACCEPT, "text/event-stream")
+        };
+        let res = self.send(request, true).await?;
+        Ok(EventStream {
+            response: check_status(res).await?,
+            parser: sse::Parser::default(),
+            pending: VecDeque::new(),
+        })
     }
 
     pub async fn submit_simulation(
         &self,
         simulation: &simulation::Simulation,
-    ) -> Result<SubmittedSimulation, reqwest::Error> {
-        self.client
-            .put(self.with_path("/simulation"))
-            .json(simulation)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await
+    ) -> Result<SubmittedSimulation, Error> {
+        let url = self.with_path(&["simulation"]);
+        // the same key for every attempt, so the server runs the simulation only once
+        let key = self.policy.idempotent_submit.then(Uuid::new_v4);
+        let request = || {
+            let request = self
+                .client
+                .put(url.clone())
+                .timeout(self.policy.timeout)
+                .json(simulation);
+            match key {
+                Some(key) => request.header("Idempotency-Key", key.to_string()),
+                None => request,
+            }
+        };
+        read_json(self.send(request, key.is_some()).await?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_keep_the_base_and_encode_device_names() {
+        let id = Uuid::nil();
+        let log = |base: &str, device: &str| {
+            let device = DeviceName::try_new(device).unwrap();
+            let url = with_path(
+                &Url::parse(base).unwrap(),
+                &["simulation", &id.to_string(), "log", device.as_str()],
+            );
+            url.to_string()
+        };
+        let prefix = format!("https://host/api/simulation/{id}/log");
+        for base in ["https://host/api", "https://host/api/"] {
+            assert_eq!(log(base, "Battery"), format!("{prefix}/Battery"));
+        }
+        assert_eq!(
+            log("https://host/api/", "Solar Panel 1"),
+            format!("{prefix}/Solar%20Panel%201")
+        );
+        assert_eq!(log("https://host/api/", "a/b"), format!("{prefix}/a%2Fb"));
+        assert_eq!(
+            log("https://host/api/", "why?#not"),
+            format!("{prefix}/why%3F%23not")
+        );
+        assert_eq!(log("https://host/api/", "100%"), format!("{prefix}/100%25"));
+        assert_eq!(
+            with_path(
+                &Url::parse("http://localhost:8000").unwrap(),
+                &["simulation"]
+            )
+            .as_str(),
+            "http://localhost:8000/simulation"
+        );
+    }
+
+    #[test]
+    fn offline_list_comes_from_the_cache() {
+        let endpoint = Url::parse("http://host/api/").unwrap();
+        let client = Client::new(
+            endpoint.clone(),
+            RequestPolicy::default(),
+            Arc::new(auth::Session::new(endpoint.to_string())),
+            &tls::TlsConfig::default(),
+            Arc::new(Cache::new(1024, None)),
+        )
+        .unwrap();
+        assert!(client.cached_simulations_page(10).is_none());
+
+        let overview = r#"{"id":"67e55044-10b1-426f-9247-bb680e5fe0c8","timestamp":0}"#;
+        client.cache.insert_tagged(
+            "http://host/api/simulation",
+            None,
+            format!("[{overview}]").into(),
+        );
+        assert_eq!(
+            client
+                .cached_simulations_page(10)
+                .unwrap()
+                .simulations
+                .len(),
+            1
+        );
+
+        client.cache.insert_tagged(
+            "http://host/api/simulation?limit=10",
+            Some("\"1\"".to_string()),
+            format!(r#"{{"items":[{overview},{overview}],"next":"abc"}}"#).into(),
+        );
+        let page = client.cached_simulations_page(10).unwrap();
+        assert_eq!(page.simulations.len(), 2);
+        assert!(page.next.is_none());
+    }
+
+    #[test]
+    fn device_logs_of_finished_simulations_come_from_the_cache() {
+        let endpoint = Url::parse("http://host/").unwrap();
+        let client = Client::new(
+            endpoint.clone(),
+            RequestPolicy::default(),
+            Arc::new(auth::Session::new(endpoint.to_string())),
+            &tls::TlsConfig::default(),
+            Arc::new(Cache::new(1024, None)),
+        )
+        .unwrap();
+        let id = Uuid::nil();
+        client
+            .cache
+            .insert(&format!("http://host/simulation/{id}"), "{}".into());
+        let devices: Vec<_> = (1..=6)
+            .map(|i| DeviceName::try_new(format!("Panel {i}")).unwrap())
+            .collect();
+        for device in &devices {
+            let url = format!("http://host/simulation/{id}/log/{}", device.as_str());
+            client
+                .cache
+                .insert(&url.replace(' ', "%20"), device.as_str().into());
+        }
+
+        let mut logs: Vec<_> =
+            futures::executor::block_on(client.fetch_all_device_logs(id, &devices).collect());
+        logs.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+        assert_eq!(logs.len(), devices.len());
+        for (device, log) in logs {
+            assert_eq!(log.unwrap(), device.as_str());
+        }
+    }
+
+    #[test]
+    fn page_falls_back_to_plain_list() {
+        let overview = r#"{"id":"67e55044-10b1-426f-9247-bb680e5fe0c8","timestamp":0}"#;
+        let paged: PageResponse =
+            serde_json::from_str(&format!(r#"{{"items":[{overview}],"next":"abc"}}"#)).unwrap();
+        let page = SimulationPage::from(paged);
+        assert_eq!(
+            (page.simulations.len(), page.next.as_deref()),
+            (1, Some("abc"))
+        );
+
+        let all: PageResponse = serde_json::from_str(&format!("[{overview},{overview}]")).unwrap();
+        let page = SimulationPage::from(all);
+        assert_eq!((page.simulations.len(), page.next), (2, None));
+    }
+
+    #[test]
+    fn parses_simulation_events() {
+        let event = |event: &str, data: &str| {
+            SimulationEvent::from_sse(&sse::Event {
+                event: event.to_string(),
+                data: data.to_string(),
+            })
+        };
+        let status = event("status", r#"{"state":"running","tick":3,"totalTicks":96}"#);
+        assert!(matches!(
+            status,
+            Some(Ok(SimulationEvent::Status(SimulationStatus {
+                state: RunState::Running,
+                tick: 3,
+                total_ticks: Some(96),
+            })))
+        ));
+        assert!(matches!(
+            event("log", r#"{"device":"Battery","line":"{}"}"#),
+            Some(Ok(SimulationEvent::Log(_)))
+        ));
+        let long = "x".repeat(crate::domain::MAX_DEVICE_NAME_LEN + 1);
+        let data = format!(r#"{{"device":"{long}","line":"{{}}"}}"#);
+        assert!(matches!(
+            event("log", &data),
+            Some(Ok(SimulationEvent::Log(log))) if log.device.as_str() == long
+        ));
+        assert!(matches!(event("finished", "{}"), Some(Err(_))));
+        assert!(event("ping", "").is_none());
+    }
+
+    #[test]
+    fn retry_waits_grow_and_follow_the_server() {
+        assert_eq!(backoff(0), RETRY_DELAY);
+        assert_eq!(backoff(2), RETRY_DELAY * 4);
+        assert_eq!(backoff(20), MAX_RETRY_DELAY);
+
+        let now: jiff::Timestamp = "2024-11-06T08:49:30Z".parse().unwrap();
+        assert_eq!(retry_after("5", now), Some(Duration::from_secs(5)));
+        assert_eq!(retry_after("3600", now), Some(MAX_RETRY_DELAY));
+        assert_eq!(
+            retry_after("Wed, 06 Nov 2024 08:49:37 GMT", now),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            retry_after("Wed, 06 Nov 2024 08:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after("soon", now), None);
+
+        assert!(retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(retryable(StatusCode::BAD_GATEWAY));
+        assert!(!retryable(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn parses_problem_details() {
+        let body = r#"{
+            "type": "about:blank",
+            "title": "Invalid simulation",
+            "status": 422,
+            "errors": [
+                {"pointer": "/devices/1/name", "detail": "duplicate device name"},
+                {"message": "start is after end"}
+            ]
+        }"#;
+        let problem = Problem::parse(body).unwrap();
+        assert_eq!(problem.summary(), Some("Invalid simulation"));
+        let errors = problem
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                "/devices/1/name: duplicate device name",
+                "start is after end"
+            ]
+        );
+
+        assert_eq!(Problem::parse(r#"{"status":500}"#), None);
+        assert_eq!(Problem::parse("Internal Server Error"), None);
+    }
+
+    #[test]
+    fn reports_keep_device_names_the_editor_rejects() {
+        let sim: Simulation =
+            decode_json(r#"{"devices":[" Temp °C ","Fryer ©","Battery 1"],"res":{"result":42}}"#)
+                .unwrap();
+        let names = sim
+            .devices
+            .iter()
+            .map(DeviceName::as_str)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Temp °C", "Fryer ©", "Battery 1"]);
+        assert!(DeviceName::try_new("Temp °C").is_err());
+    }
+
+    #[test]
+    fn status_errors_show_the_body() {
+        let error = |body: &str| Error::Status {
+            code: StatusCode::UNPROCESSABLE_ENTITY,
+            body: body.to_string(),
+            problem: Problem::parse(body),
+        };
+        assert_eq!(
+            error(r#"{"title":"Invalid","detail":"Unknown device type Foo"}"#).to_string(),
+            "Server responded with 422 Unprocessable Entity: Unknown device type Foo"
+        );
+        assert_eq!(
+            error(&"x".repeat(500)).to_string().len(),
+            "Server responded with 422 Unprocessable Entity: ".len() + SNIPPET_LEN
+        );
+        assert_eq!(
+            error("").to_string(),
+            "Server responded with 422 Unprocessable Entity"
+        );
     }
 }
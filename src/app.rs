@@ -1,19 +1,44 @@
 use cosmic::app::{context_drawer, Core, Task};
+use cosmic::cosmic_config::{self, CosmicConfigEntry};
 use cosmic::dialog::ashpd::desktop::file_chooser::FileFilter;
 use cosmic::dialog::file_chooser::{self};
-use cosmic::iced::{Alignment, Length, Subscription};
+use cosmic::iced::keyboard::{key::Named, Key, Modifiers};
+use cosmic::iced::task;
+use cosmic::iced::widget::scrollable;
+use cosmic::iced::{event, keyboard, window, Alignment, Event, Length, Subscription};
 use cosmic::widget::{self, menu, nav_bar};
 use cosmic::{cosmic_theme, theme, Application, ApplicationExt, Element};
 use reqwest::Url;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 use crate::api;
+use crate::auth::{self, AuthMethod, OAuthFlow};
+use crate::cache::{self, Cache};
+use crate::cli;
+use crate::config::{Config, QueuedSubmission, ServerProfile, State, Submission};
+use crate::diff::{self, Comparison, RowKind};
 use crate::domain::DeviceName;
-use crate::simulation::Simulation;
+use crate::export::{self, ExportFormat};
+use crate::history::History;
+use crate::key_binds;
+use crate::list_filter::{ListFilter, SortOrder};
+use crate::live::LiveRun;
+use crate::metadata::{self, SimulationMeta};
+use crate::notification;
+use crate::palette;
+use crate::refresh;
+use crate::report;
+use crate::simulation::{self, Simulation};
+use crate::table::{ParsedText, Table};
+use crate::tls;
+use crate::virtual_list::{self, ROW_HEIGHT};
 
-const REPOSITORY: &str = "https://github.com/pop-os/cosmic-app-template";
+const REPOSITORY: &str = "https://github.com/fhdo7100003/ha-ui";
 const APP_ICON: &[u8] = include_bytes!("../res/icons/hicolor/scalable/apps/icon.svg");
 
 /// The application model stores app-specific state used to describe its interface and
@@ -21,14 +46,112 @@ const APP_ICON: &[u8] = include_bytes!("../res/icons/hicolor/scalable/apps/icon.
 pub struct AppModel {
     core: Core,
     context_page: ContextPage,
+    config: Config,
+    config_handler: Option<cosmic_config::Config>,
+    state: State,
+    state_handler: Option<cosmic_config::Config>,
     key_binds: HashMap<menu::KeyBind, MenuAction>,
+    /// Bindings typed into the settings page which haven't been applied yet.
+    key_bind_inputs: HashMap<MenuAction, String>,
+    /// PDF converter being edited, saved once submitted.
+    pdf_command_input: Option<String>,
+    key_bind_error: Option<String>,
+    palette: Option<CommandPalette>,
+    palette_input: widget::Id,
     nav_model: nav_bar::Model,
     client: Arc<api::Client>,
+    /// Command line options, which override the settings.
+    args: cli::Args,
+    /// Names of the server profiles, for the switcher.
+    profile_names: Vec<String>,
+    /// The token or password being entered, until it is stored in the keyring.
+    auth_secret: String,
+    /// Whether credentials for the server are stored.
+    has_credentials: bool,
+    logging_in: bool,
+    /// The code to enter while a device login waits for the user.
+    device_login: Option<auth::DeviceLogin>,
+    /// Password of a PKCS#12 client certificate, `None` until the keyring was asked.
+    certificate_password: Option<String>,
+    /// The certificate password being entered.
+    certificate_password_input: String,
+    /// Why the active profile couldn't be connected, nothing is fetched or submitted then.
+    connect_error: Option<String>,
     simulations: Vec<api::SimulationOverview>,
+    /// Reports of listed simulations, used for searching and sorting by result.
+    details: HashMap<Uuid, api::Simulation>,
+    /// Simulations whose report is being fetched for [`Self::details`].
+    details_pending: HashSet<Uuid>,
+    list_filter: ListFilter,
+    list_from_input: String,
+    list_to_input: String,
+    /// Labels of [`SortOrder::ALL`] for the sort dropdown.
+    sort_labels: Vec<&'static str>,
+    /// Used to group the simulation list by day.
+    time_zone: jiff::tz::TimeZone,
+    /// Cursor of the next page of the simulation list, `None` once all are loaded.
+    next_page: Option<String>,
+    loading_more: bool,
+    list_viewport: Option<scrollable::Viewport>,
+    focused: bool,
+    refreshing: bool,
+    /// Whether a queued submission is being sent.
+    flushing: bool,
+    /// Failed checks for new simulations in a row, used to back off.
+    refresh_failures: u32,
+    /// Simulations which appeared since they were last looked at.
+    new_simulations: HashSet<Uuid>,
+    /// The simulation whose progress is followed on the live page.
+    live: Option<LiveRun>,
+    /// Charts of the live run per device, redrawn as ticks complete.
+    live_charts: Vec<(String, widget::svg::Handle)>,
+    submitting: bool,
+    /// Failures saving or applying settings, shown as toasts once the message is handled.
+    failures: Vec<String>,
+    /// Simulations the shown desktop notifications are about, by notification id.
+    notifications: HashMap<u32, Uuid>,
+    /// A list row action waiting for confirmation.
+    confirm: Option<RowAction>,
+    /// Metadata of the selected simulation as it is being edited.
+    meta_draft: SimulationMeta,
+    meta_tags_input: String,
+    meta_notes: widget::text_editor::Content,
     editor_content: widget::text_editor::Content,
+    /// The file the editor content was opened from or last saved to.
+    editor_path: Option<PathBuf>,
+    editor_history: History,
+    /// Whether the last edit was typing a word, consecutive typing is undone at once.
+    editor_typing: bool,
     selected_simulation: Option<(Uuid, api::Simulation)>,
+    /// Requests filling the simulation list, the details and the text panel.
+    list_request: LatestRequest,
+    detail_request: LatestRequest,
+    text_request: LatestRequest,
+    /// Logs of every device of the selected simulation, once fetched.
+    device_logs: Option<DeviceLogs>,
+    logs_request: LatestRequest,
     text_display: Option<String>,
+    /// Comparison shown in the text panel, replaced by any text shown there.
+    comparison: Option<Comparison>,
+    /// Simulation picked to compare others with.
+    compare_base: Option<Uuid>,
+    /// Whether comparisons are shown as a unified diff rather than side by side.
+    unified_diff: bool,
+    /// `text_display` parsed as JSON, if it is JSON.
+    output_json: Option<serde_json::Value>,
+    /// `text_display` parsed as a log table, if it is a log.
+    output_table: Option<Table>,
     has_error: Option<String>,
+    /// What the server found wrong with the last submitted simulation.
+    validation: Vec<api::FieldError>,
+    toasts: widget::Toasts<Message>,
+    export_format: ExportFormat,
+    /// Labels of [`ExportFormat::ALL`] for the format dropdown.
+    export_format_labels: Vec<&'static str>,
+    exporting: bool,
+    /// Simulations fetched so far and in total while exporting all.
+    export_progress: Option<(usize, usize)>,
+    generating_report: bool,
 }
 
 /// Messages emitted by the application and its widgets.
@@ -37,19 +160,150 @@ pub enum Message {
     OpenRepositoryUrl,
     ToggleContextPage(ContextPage),
     FetchAllSimulations,
-    SimulationsFetched(Vec<api::SimulationOverview>),
+    SimulationsFetched(u64, Result<api::SimulationPage, String>),
+    LoadMoreSimulations,
+    MoreSimulationsFetched(u64, Result<api::SimulationPage, String>),
+    ListScrolled(scrollable::Viewport),
+    WindowFocused(bool),
+    AutoRefresh,
+    AutoRefreshed(u64, Result<api::SimulationPage, String>),
+    RefreshIntervalSelected(usize),
+    WatchSimulation(Uuid),
+    LiveEvent(Uuid, Result<api::SimulationEvent, String>),
+    LiveEnded(Uuid),
+    StopWatching,
+    /// The result of a submission, with the editor file it came from.
+    ///
+    /// Errors come with the server's validation errors, if it sent any.
+    Submitted(
+        Option<PathBuf>,
+        Result<api::SubmittedSimulation, (String, Vec<api::FieldError>)>,
+    ),
+    ConfirmRowAction(RowAction),
+    CancelRowAction,
+    RunRowAction,
+    Deleted(Uuid, Result<(), String>),
+    ClonedSource(Result<String, String>),
+    ShowSubmission(Uuid),
+    Notified(Option<Uuid>, Result<u32, String>),
+    OpenNotification(u32),
+    NotificationClosed(u32),
+    Notifications(bool),
+    /// Report of a listed simulation, `None` if fetching it failed.
+    DetailsFetched(Uuid, Option<api::Simulation>),
+    ListQuery(String),
+    ListFrom(String),
+    ListTo(String),
+    ListSortSelected(usize),
+    ListGroupByDay(bool),
+    MetaName(String),
+    MetaTags(String),
+    MetaNotes(widget::text_editor::Action),
+    MetaSave,
+    MetaSynced(Result<(), String>),
+    SyncMetadata(bool),
     OpenFile,
+    OpenRecent(usize),
+    FileOpened(Result<Option<(PathBuf, String)>, String>),
+    Save,
+    SaveAs,
+    Saved(Result<Option<PathBuf>, String>),
+    ImportCsv,
+    CsvImported(Result<Option<String>, String>),
+    ExportResults,
+    ExportAll,
+    ExportFormatSelected(usize),
+    /// Simulations fetched so far and in total by "Export all".
+    ExportProgress(usize, usize),
+    Exported(Result<Option<PathBuf>, String>),
+    GenerateReport,
+    ReportGenerated(Result<Option<PathBuf>, String>),
+    ChooseReportTemplate,
+    ReportTemplateChosen(Result<Option<PathBuf>, String>),
+    ResetReportTemplate,
+    PdfCommandInput(String),
+    PdfCommandSubmit,
+    Quit,
     Edit(widget::text_editor::Action),
+    Undo,
+    Redo,
+    FormatJson,
     SelectSimulation(Uuid),
-    FetchedSimulation(Uuid, api::Simulation),
+    NextSimulation,
+    PreviousSimulation,
+    FetchedSimulation(u64, Uuid, Result<api::Simulation, String>),
     ShowSource(Uuid),
     ShowDeviceLog(Uuid, DeviceName),
     ShowAllDeviceLog(Uuid),
-    FetchedText(Uuid, String),
+    FetchAllDeviceLogs(Uuid),
+    DeviceLogFetched(u64, DeviceName, Result<ParsedText, String>),
+    FetchedText(u64, Result<ParsedText, String>),
+    PickForComparison(Uuid),
+    /// Compares the first simulation's source with the second's.
+    CompareRuns(Uuid, Uuid),
+    CompareWithDraft(Uuid),
+    Compared(u64, Result<Comparison, String>),
+    UnifiedDiff(bool),
+    CloseComparison,
     NewSimulation,
     Submit,
     CopyTextToClipboard,
+    CopyAs(CopyFormat),
+    CloseToast(widget::ToastId),
     ReplaceEditorContent(String),
+    Key(Modifiers, Key),
+    UpdateConfig(Config),
+    ConnectTimeoutSelected(usize),
+    RequestTimeoutSelected(usize),
+    RetriesSelected(usize),
+    IdempotentSubmit(bool),
+    /// Sends the oldest simulation queued for the server while it was unreachable.
+    FlushQueue,
+    QueuedSubmitted(QueuedSubmission, Result<api::SubmittedSimulation, String>),
+    /// A queued simulation couldn't reach the server, so it surely didn't run.
+    QueuedUnsent(QueuedSubmission),
+    DiskCache(bool),
+    ClearCache,
+    CacheCleared(Result<(), String>),
+    SwitchProfile(usize),
+    AddProfile,
+    RemoveProfile(usize),
+    ProfileName(usize, String),
+    ProfileEndpoint(usize, String),
+    AuthMethodSelected(usize),
+    AuthUsername(String),
+    AuthSecretInput(String),
+    OAuthIssuer(String),
+    OAuthClientId(String),
+    OAuthScope(String),
+    OAuthFlowSelected(usize),
+    SaveCredentials,
+    /// Credentials and certificate password loaded for a server, the list of which is
+    /// fetched next.
+    SecretsLoaded(
+        String,
+        Result<(Option<auth::Credentials>, Option<String>), String>,
+    ),
+    CredentialsStored(String, Result<auth::Credentials, String>),
+    Login,
+    DeviceLoginStarted(Result<auth::DeviceLogin, String>),
+    OpenVerificationPage,
+    Logout,
+    LoggedOut(Result<(), String>),
+    ChooseTlsFile(TlsFile),
+    TlsFileChosen(TlsFile, Result<Option<PathBuf>, String>),
+    ResetTlsFile(TlsFile),
+    AcceptInvalidCerts(bool),
+    CertificatePasswordInput(String),
+    SaveCertificatePassword,
+    CertificatePasswordStored(String, Result<String, String>),
+    KeyBindInput(MenuAction, String),
+    KeyBindSubmit(MenuAction),
+    KeyBindReset(MenuAction),
+    TogglePalette,
+    PaletteInput(String),
+    PaletteSubmit,
+    PaletteActivate(PaletteEntry),
 }
 
 const DEFAULT_SIMULATION: &str = include_str!("../res/example_simulation.json");
@@ -60,7 +314,7 @@ impl Application for AppModel {
     type Executor = cosmic::executor::Default;
 
     /// Data that your application receives to its init method.
-    type Flags = ();
+    type Flags = cli::Args;
 
     /// Messages which the application and its widgets will emit.
     type Message = Message;
@@ -77,63 +331,292 @@ impl Application for AppModel {
     }
 
     /// Initializes the application with any given flags and startup commands.
-    fn init(core: Core, _flags: Self::Flags) -> (Self, Task<Self::Message>) {
+    fn init(core: Core, args: Self::Flags) -> (Self, Task<Self::Message>) {
         let mut nav = nav_bar::Model::default();
 
+        // the list is shown at start, fetching it later never switches pages
         nav.insert()
             .text("List simulations")
-            .data::<Page>(Page::SimulationList);
+            .data::<Page>(Page::SimulationList)
+            .activate();
 
         nav.insert()
             .text("Create simulation")
             .data::<Page>(Page::NewSimulation);
 
-        let app = AppModel {
+        nav.insert()
+            .text("Live progress")
+            .data::<Page>(Page::LiveRun);
+
+        let config_handler = cosmic_config::Config::new(Self::APP_ID, Config::VERSION).ok();
+        let config = config_handler
+            .as_ref()
+            .map(|handler| match Config::get_entry(handler) {
+                Ok(config) => config,
+                Err((_errors, config)) => config,
+            })
+            .unwrap_or_default();
+
+        let mut config = config;
+        if config.profiles.is_empty() {
+            config.profiles.push(ServerProfile::default());
+        }
+
+        let state_handler = cosmic_config::Config::new_state(Self::APP_ID, State::VERSION).ok();
+        let state = state_handler
+            .as_ref()
+            .map(|handler| match State::get_entry(handler) {
+                Ok(state) => state,
+                Err((_errors, state)) => state,
+            })
+            .unwrap_or_default();
+
+        // replaced by the active profile's server in `connect`
+        let endpoint = Url::parse(&ServerProfile::default().endpoint).unwrap();
+        let session = Arc::new(auth::Session::new(endpoint.to_string()));
+        let cache = Arc::new(Cache::new(
+            cache::MEMORY_BUDGET,
+            config.disk_cache.then(cache::default_dir).flatten(),
+        ));
+        let client = api::Client::new(
+            endpoint,
+            request_policy(&config, &args),
+            session,
+            &tls::TlsConfig::default(),
+            cache,
+        )
+        .expect("failed to create HTTP client");
+        let profile_names = config.profiles.iter().map(|p| p.name.clone()).collect();
+
+        let mut app = AppModel {
             core,
             context_page: ContextPage::default(),
             nav_model: nav,
-            key_binds: HashMap::new(),
-            client: Arc::new(api::Client {
-                endpoint: Url::parse("http://localhost:8000").unwrap(),
-                client: reqwest::Client::new(),
-            }),
+            key_binds: key_binds::key_binds(&config.key_binds),
+            key_bind_inputs: HashMap::new(),
+            pdf_command_input: None,
+            key_bind_error: None,
+            config,
+            config_handler,
+            state,
+            state_handler,
+            palette: None,
+            palette_input: widget::Id::unique(),
+            client: Arc::new(client),
+            args,
+            profile_names,
+            auth_secret: String::new(),
+            has_credentials: false,
+            logging_in: false,
+            device_login: None,
+            certificate_password: None,
+            certificate_password_input: String::new(),
+            connect_error: None,
             simulations: Vec::new(),
+            details: HashMap::new(),
+            details_pending: HashSet::new(),
+            list_filter: ListFilter::default(),
+            list_from_input: String::new(),
+            list_to_input: String::new(),
+            sort_labels: SortOrder::ALL.iter().map(|s| s.label()).collect(),
+            time_zone: jiff::tz::TimeZone::system(),
+            next_page: None,
+            loading_more: false,
+            list_viewport: None,
+            focused: true,
+            refreshing: false,
+            flushing: false,
+            refresh_failures: 0,
+            new_simulations: HashSet::new(),
+            live: None,
+            live_charts: Vec::new(),
+            submitting: false,
+            failures: Vec::new(),
+            notifications: HashMap::new(),
+            confirm: None,
+            meta_draft: SimulationMeta::default(),
+            meta_tags_input: String::new(),
+            meta_notes: widget::text_editor::Content::new(),
             editor_content: widget::text_editor::Content::with_text(DEFAULT_SIMULATION),
+            editor_path: None,
+            editor_history: History::default(),
+            editor_typing: false,
             selected_simulation: None,
+            list_request: LatestRequest::default(),
+            detail_request: LatestRequest::default(),
+            text_request: LatestRequest::default(),
+            device_logs: None,
+            logs_request: LatestRequest::default(),
             text_display: None,
+            comparison: None,
+            compare_base: None,
+            unified_diff: false,
+            output_json: None,
+            output_table: None,
             has_error: None,
+            validation: Vec::new(),
+            toasts: widget::Toasts::new(Message::CloseToast),
+            export_format: ExportFormat::default(),
+            export_format_labels: ExportFormat::ALL.iter().map(|f| f.label()).collect(),
+            exporting: false,
+            export_progress: None,
+            generating_report: false,
         };
 
-        let client = app.client.clone();
-        (
-            app,
-            Task::perform(
-                async move { client.fetch_all_simulations().await.unwrap() },
-                |res| Message::SimulationsFetched(res).into(),
-            ),
-        )
+        let connect = app.connect();
+        (app, connect)
     }
 
     /// Elements to pack at the start of the header bar.
     fn header_start(&self) -> Vec<Element<Self::Message>> {
+        let recent_files = if self.config.recent_files.is_empty() {
+            vec![menu::Item::ButtonDisabled(
+                "No recent files".to_string(),
+                None,
+                MenuAction::OpenRecent(0),
+            )]
+        } else {
+            self.config
+                .recent_files
+                .iter()
+                .enumerate()
+                .map(|(i, path)| {
+                    menu::Item::Button(path.display().to_string(), None, MenuAction::OpenRecent(i))
+                })
+                .collect()
+        };
+
+        let undo = if self.editor_history.can_undo() {
+            menu::Item::Button("Undo", None, MenuAction::Undo)
+        } else {
+            menu::Item::ButtonDisabled("Undo", None, MenuAction::Undo)
+        };
+        let redo = if self.editor_history.can_redo() {
+            menu::Item::Button("Redo", None, MenuAction::Redo)
+        } else {
+            menu::Item::ButtonDisabled("Redo", None, MenuAction::Redo)
+        };
+
+        let export = menu_button(
+            "Export results".to_string(),
+            MenuAction::ExportResults,
+            self.selected_simulation.is_some() && !self.exporting,
+        );
+        let report = menu_button(
+            "Generate report".to_string(),
+            MenuAction::GenerateReport,
+            self.selected_simulation.is_some() && !self.generating_report,
+        );
+        let export_all = menu_button(
+            "Export all simulations".to_string(),
+            MenuAction::ExportAll,
+            !self.simulations.is_empty() && !self.exporting,
+        );
+
         let menu_bar = menu::bar(vec![
             menu::Tree::with_children(
                 menu::root("File"),
                 menu::items(
                     &self.key_binds,
-                    vec![menu::Item::Button("Open", None, MenuAction::OpenFile)],
+                    vec![
+                        menu::Item::Button("New".to_string(), None, MenuAction::NewFromTemplate),
+                        menu::Item::Button("Open".to_string(), None, MenuAction::OpenFile),
+                        menu::Item::Folder("Open recent".to_string(), recent_files),
+                        menu::Item::Button("Save".to_string(), None, MenuAction::Save),
+                        menu::Item::Button("Save as".to_string(), None, MenuAction::SaveAs),
+                        menu::Item::Divider,
+                        menu::Item::Button("Import CSV".to_string(), None, MenuAction::ImportCsv),
+                        export,
+                        export_all,
+                        report,
+                        menu::Item::Divider,
+                        menu::Item::Button("Submit".to_string(), None, MenuAction::Submit),
+                        menu::Item::Divider,
+                        menu::Item::Button("Quit".to_string(), None, MenuAction::Quit),
+                    ],
+                ),
+            ),
+            menu::Tree::with_children(
+                menu::root("Edit"),
+                menu::items(
+                    &self.key_binds,
+                    vec![
+                        undo,
+                        redo,
+                        menu::Item::Divider,
+                        menu::Item::Folder(
+                            "Copy output as",
+                            CopyFormat::ALL
+                                .into_iter()
+                                .map(|format| {
+                                    let action = MenuAction::CopyAs(format);
+                                    if self.can_copy_as(format) {
+                                        menu::Item::Button(format.label(), None, action)
+                                    } else {
+                                        menu::Item::ButtonDisabled(format.label(), None, action)
+                                    }
+                                })
+                                .collect(),
+                        ),
+                        menu::Item::Button("Format JSON", None, MenuAction::FormatJson),
+                    ],
                 ),
             ),
             menu::Tree::with_children(
                 menu::root("View"),
                 menu::items(
                     &self.key_binds,
-                    vec![menu::Item::Button("About", None, MenuAction::About)],
+                    vec![
+                        menu::Item::Button("Refresh", None, MenuAction::RefreshList),
+                        menu::Item::Button("Clear cache", None, MenuAction::ClearCache),
+                        menu::Item::Button("Next simulation", None, MenuAction::NextSimulation),
+                        menu::Item::Button(
+                            "Previous simulation",
+                            None,
+                            MenuAction::PreviousSimulation,
+                        ),
+                        menu::Item::Button("Command palette", None, MenuAction::CommandPalette),
+                        menu::Item::Divider,
+                        menu::Item::Button("Settings", None, MenuAction::ToggleSettings),
+                        menu::Item::Button("About", None, MenuAction::About),
+                    ],
                 ),
             ),
         ]);
 
-        vec![menu_bar.into()]
+        let profiles = widget::dropdown(
+            &self.profile_names,
+            Some(self.config.profile_index()),
+            Message::SwitchProfile,
+        );
+
+        let mut elements = vec![menu_bar.into(), profiles.into()];
+        if self.connect_error.is_some() {
+            elements.push(status_line(
+                "network-error-symbolic",
+                "Not connected".to_string(),
+            ));
+        } else if self.client.offline() {
+            let server = self.client.auth.server();
+            let label = match self
+                .state
+                .queued
+                .iter()
+                .filter(|q| q.server == server)
+                .count()
+            {
+                0 => "Offline".to_string(),
+                queued => format!("Offline, {queued} queued"),
+            };
+            elements.push(status_line("network-offline-symbolic", label));
+        }
+        if self.config.profile().tls.accept_invalid_certs {
+            elements.push(status_line(
+                "dialog-warning-symbolic",
+                "Certificates not verified".to_string(),
+            ));
+        }
+        elements
     }
 
     fn context_drawer(&self) -> Option<context_drawer::ContextDrawer<Self::Message>> {
@@ -147,9 +630,29 @@ impl Application for AppModel {
                 Message::ToggleContextPage(ContextPage::About),
             )
             .title("About"),
+            ContextPage::Settings => context_drawer::context_drawer(
+                self.settings(),
+                Message::ToggleContextPage(ContextPage::Settings),
+            )
+            .title("Settings"),
         })
     }
 
+    fn dialog(&self) -> Option<Element<Self::Message>> {
+        if let Some(action) = self.confirm {
+            return Some(self.confirm_view(action));
+        }
+        self.palette
+            .as_ref()
+            .map(|palette| self.palette_view(palette))
+    }
+
+    fn on_escape(&mut self) -> Task<Self::Message> {
+        self.palette = None;
+        self.confirm = None;
+        Task::none()
+    }
+
     fn nav_model(&self) -> Option<&nav_bar::Model> {
         Some(&self.nav_model)
     }
@@ -171,103 +674,332 @@ impl Application for AppModel {
         } = theme::active().cosmic().spacing;
 
         let page = self.nav_model.data::<Page>(self.nav_model.active());
-        match page {
+        let content: Element<Self::Message> = match page {
             Some(Page::SimulationList) => widget::row()
-                .push(
-                    widget::scrollable(widget::column::with_children(
-                        self.simulations
-                            .iter()
-                            .map(|sim| {
-                                widget::button::custom(
-                                    widget::column()
-                                        .push(widget::text(sim.id.to_string()))
-                                        .push(widget::text(sim.timestamp.to_string())),
+                .push(widget::container(self.simulation_list()).width(Length::FillPortion(1)))
+                .push_maybe(self.detail_request.loading().then(|| {
+                    widget::container(loading("Loading simulation…")).width(Length::FillPortion(1))
+                }))
+                // until the newly selected simulation arrives
+                .push_maybe(
+                    self.selected_simulation
+                        .as_ref()
+                        .filter(|_| !self.detail_request.loading())
+                        .map(|(id, sim)| {
+                            let meta = self.state.metadata.get(id);
+                            widget::column()
+                                .push(widget::text::heading(SimulationMeta::display_name(
+                                    meta, *id,
+                                )))
+                                .push_maybe(
+                                    meta.is_some_and(|meta| !meta.name.is_empty())
+                                        .then(|| widget::text::caption(id.to_string())),
                                 )
-                                .on_press(Message::SelectSimulation(sim.id))
-                                .width(Length::Fill)
-                                .into()
-                            })
-                            .collect::<Vec<_>>(),
-                    ))
-                    .width(Length::FillPortion(1)),
+                                .push(self.metadata_editor())
+                                .push(
+                                    widget::row()
+                                        .push(widget::text("Result"))
+                                        .push(widget::text(format!("{} Wh", sim.res.result))),
+                                )
+                                .push(
+                                    widget::row()
+                                        .push(
+                                            widget::button::text("Show source simulation")
+                                                .on_press(Message::ShowSource(*id)),
+                                        )
+                                        .push(
+                                            widget::button::text("Watch live")
+                                                .on_press(Message::WatchSimulation(*id)),
+                                        )
+                                        .spacing(space_xxs),
+                                )
+                                .push(
+                                    widget::row()
+                                        .push(
+                                            widget::button::text("Compare with draft")
+                                                .on_press(Message::CompareWithDraft(*id)),
+                                        )
+                                        .push(
+                                            widget::button::text("Pick for comparison")
+                                                .on_press_maybe(
+                                                    (self.compare_base != Some(*id))
+                                                        .then_some(Message::PickForComparison(*id)),
+                                                ),
+                                        )
+                                        .push_maybe(
+                                            self.compare_base.filter(|base| base != id).map(
+                                                |base| {
+                                                    let name = SimulationMeta::display_name(
+                                                        self.state.metadata.get(&base),
+                                                        base,
+                                                    );
+                                                    widget::button::text(format!(
+                                                        "Compare with {name}"
+                                                    ))
+                                                    .on_press(Message::CompareRuns(base, *id))
+                                                },
+                                            ),
+                                        )
+                                        .spacing(space_xxs),
+                                )
+                                .push(
+                                    widget::row()
+                                        .push(widget::dropdown(
+                                            &self.export_format_labels,
+                                            ExportFormat::ALL
+                                                .iter()
+                                                .position(|f| *f == self.export_format),
+                                            Message::ExportFormatSelected,
+                                        ))
+                                        .push(widget::button::text("Export").on_press_maybe(
+                                            (!self.exporting).then_some(Message::ExportResults),
+                                        ))
+                                        .push(widget::button::text("Export all").on_press_maybe(
+                                            (!self.exporting).then_some(Message::ExportAll),
+                                        ))
+                                        .push(
+                                            widget::button::text("Generate report").on_press_maybe(
+                                                (!self.generating_report)
+                                                    .then_some(Message::GenerateReport),
+                                            ),
+                                        )
+                                        .align_y(Alignment::Center)
+                                        .spacing(space_xxs),
+                                )
+                                .push(
+                                    widget::row()
+                                        .push(widget::text("Device Log"))
+                                        .push(
+                                            widget::button::text("Show all")
+                                                .on_press(Message::ShowAllDeviceLog(*id)),
+                                        )
+                                        .push(
+                                            widget::button::text("Fetch each device")
+                                                .on_press_maybe(
+                                                    (!self.logs_request.loading()).then_some(
+                                                        Message::FetchAllDeviceLogs(*id),
+                                                    ),
+                                                ),
+                                        )
+                                        .align_y(Alignment::Center)
+                                        .spacing(space_xxs),
+                                )
+                                .push_maybe(
+                                    self.device_logs
+                                        .as_ref()
+                                        .filter(|logs| logs.id == *id)
+                                        .map(device_logs_progress),
+                                )
+                                .push(widget::scrollable(widget::column::with_children(
+                                    sim.devices
+                                        .iter()
+                                        .map(|dev| {
+                                            widget::button::custom(widget::text(dev.as_str()))
+                                                .on_press(Message::ShowDeviceLog(*id, dev.clone()))
+                                                .width(Length::Fill)
+                                                .into()
+                                        })
+                                        .collect::<Vec<_>>(),
+                                )))
+                                .spacing(space_xxs)
+                                .width(Length::FillPortion(1))
+                        }),
                 )
-                .push_maybe(self.selected_simulation.as_ref().map(|(id, sim)| {
-                    widget::column()
-                        .push(widget::text(id.to_string()))
-                        .push(
-                            widget::row()
-                                .push(widget::text("Result"))
-                                .push(widget::text(format!("{} Wh", sim.res.result))),
-                        )
-                        .push(
-                            widget::button::text("Show source simulation")
-                                .on_press(Message::ShowSource(*id)),
-                        )
-                        .push(
-                            widget::row()
-                                .push(widget::text("Device Log"))
+                .push_maybe(self.text_request.loading().then(|| loading("Loading…")))
+                .push_maybe(
+                    self.text_display
+                        .as_ref()
+                        .filter(|_| !self.text_request.loading())
+                        .map(|text| {
+                            let copy_buttons = CopyFormat::ALL.into_iter().skip(1).map(|format| {
+                                widget::button::text(format.label())
+                                    .on_press_maybe(
+                                        self.can_copy_as(format).then_some(Message::CopyAs(format)),
+                                    )
+                                    .into()
+                            });
+
+                            widget::column()
                                 .push(
-                                    widget::button::text("Show all")
-                                        .on_press(Message::ShowAllDeviceLog(*id)),
+                                    widget::row()
+                                        .push(
+                                            widget::button::icon(widget::icon::from_name(
+                                                "edit-copy-symbolic",
+                                            ))
+                                            .on_press(Message::CopyTextToClipboard),
+                                        )
+                                        .extend(copy_buttons)
+                                        .align_y(Alignment::Center)
+                                        .spacing(space_xxs),
                                 )
-                                .align_y(Alignment::Center),
-                        )
-                        .push(widget::scrollable(widget::column::with_children(
-                            sim.devices
-                                .iter()
-                                .map(|dev| {
-                                    widget::button::custom(widget::text(dev.as_str()))
-                                        .on_press(Message::ShowDeviceLog(*id, dev.clone()))
-                                        .width(Length::Fill)
-                                        .into()
-                                })
-                                .collect::<Vec<_>>(),
-                        )))
-                        .spacing(space_xxs)
-                        .width(Length::FillPortion(1))
-                }))
-                .push_maybe(self.text_display.as_ref().map(|text| {
-                    widget::column()
-                        .push(
-                            widget::button::icon(widget::icon::from_name("edit-copy-symbolic"))
-                                .on_press(Message::CopyTextToClipboard),
-                        )
-                        .push(widget::scrollable(widget::text(text)).width(Length::Fill))
-                }))
-                .spacing(space_s)
-                .into(),
-            Some(Page::NewSimulation) => widget::column()
-                .push(
-                    widget::row()
-                        .push(widget::button::text("Open").on_press(Message::OpenFile))
-                        .push(
-                            widget::button::text("Reset to template")
-                                .on_press(Message::NewSimulation),
-                        )
-                        .push(widget::button::text("Submit").on_press(Message::Submit)),
+                                .push(widget::scrollable(widget::text(text)).width(Length::Fill))
+                        }),
+                )
+                .push_maybe(
+                    self.comparison
+                        .as_ref()
+                        .filter(|_| !self.text_request.loading())
+                        .map(|comparison| self.comparison_view(comparison)),
                 )
-                .push(widget::text_editor(&self.editor_content).on_action(Message::Edit))
-                .push_maybe(self.has_error.as_ref().map(|err| widget::text::text(err)))
+                .spacing(space_s)
                 .into(),
+            Some(Page::NewSimulation) => {
+                widget::column()
+                    .push(
+                        widget::row()
+                            .push(widget::button::text("Open").on_press(Message::OpenFile))
+                            .push(
+                                widget::button::text("Reset to template")
+                                    .on_press(Message::NewSimulation),
+                            )
+                            .push(
+                                widget::button::text(if self.submitting {
+                                    "Submitting…"
+                                } else {
+                                    "Submit"
+                                })
+                                .on_press_maybe((!self.submitting).then_some(Message::Submit)),
+                            ),
+                    )
+                    .push(
+                        widget::row()
+                            .push(
+                                widget::column()
+                                    .push(
+                                        widget::text_editor(&self.editor_content)
+                                            .on_action(Message::Edit),
+                                    )
+                                    .push_maybe(
+                                        self.has_error.as_ref().map(|err| widget::text::text(err)),
+                                    )
+                                    .extend(self.validation.iter().map(|error| {
+                                        widget::text::caption(error.to_string()).into()
+                                    }))
+                                    .width(Length::FillPortion(3)),
+                            )
+                            .push(
+                                widget::container(self.submission_history())
+                                    .width(Length::FillPortion(1)),
+                            )
+                            .spacing(space_s),
+                    )
+                    .into()
+            }
+            Some(Page::LiveRun) => self.live_view(),
             None => widget::text("Select something you want to do on the left").into(),
-        }
+        };
+
+        widget::toaster(&self.toasts, content)
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
-        Subscription::batch(vec![])
+        // polling pauses while the window is in the background, but goes on while offline
+        // to notice when the server is back
+        let polling = self.focused && self.config.refresh_interval > 0;
+        let refresh =
+            (self.connect_error.is_none() && (polling || self.client.offline())).then(|| {
+                cosmic::iced::time::every(refresh::interval(
+                    self.refresh_base(),
+                    self.refresh_failures,
+                ))
+                .map(|_| Message::AutoRefresh)
+            });
+
+        // follows the live run until it ended
+        let live = self
+            .live
+            .as_ref()
+            .filter(|live| live.outcome.is_none())
+            .map(|live| live_events(self.client.clone(), live.id));
+
+        // only listens while a notification can still be clicked
+        let notifications = (!self.notifications.is_empty()).then(notification_actions);
+
+        Subscription::batch(
+            [
+                event::listen_with(|event, status, _window_id| match event {
+                    Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                        match status {
+                            event::Status::Ignored => Some(Message::Key(modifiers, key)),
+                            event::Status::Captured => None,
+                        }
+                    }
+                    Event::Window(window::Event::Focused) => Some(Message::WindowFocused(true)),
+                    Event::Window(window::Event::Unfocused) => Some(Message::WindowFocused(false)),
+                    _ => None,
+                }),
+                self.core()
+                    .watch_config::<Config>(Self::APP_ID)
+                    .map(|update| Message::UpdateConfig(update.config)),
+            ]
+            .into_iter()
+            .chain(refresh)
+            .chain(live)
+            .chain(notifications),
+        )
     }
 
     fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
+        let task = self.handle_message(message);
+        if let Some(e) = self.client.auth.take_store_error() {
+            self.failures.push(e);
+        }
+        if self.failures.is_empty() {
+            return task;
+        }
+        let toasts = std::mem::take(&mut self.failures)
+            .into_iter()
+            .map(|failure| self.toast(failure))
+            .collect::<Vec<_>>();
+        Task::batch(toasts.into_iter().chain([task]))
+    }
+}
+
+impl AppModel {
+    fn handle_message(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::Submit => {
+                if let Some(e) = &self.connect_error {
+                    return self.toast(format!("Not connected: {e}"));
+                }
                 let json = self.editor_content.text();
                 // check before sending
                 match serde_json::from_str::<Simulation>(&json) {
+                    Ok(_) if self.client.offline() => {
+                        self.has_error = None;
+                        self.validation.clear();
+                        self.state.queued.push(QueuedSubmission {
+                            server: self.client.auth.server().to_string(),
+                            simulation: json,
+                            timestamp: jiff::Timestamp::now(),
+                            file: self.editor_path.clone(),
+                        });
+                        self.save_state();
+                        return self.toast(
+                            "Offline, the simulation is submitted once the server is back"
+                                .to_string(),
+                        );
+                    }
                     Ok(sim) => {
+                        if self.submitting {
+                            return Task::none();
+                        }
+                        self.submitting = true;
+                        self.has_error = None;
+                        self.validation.clear();
                         let client = self.client.clone();
+                        let file = self.editor_path.clone();
                         return Task::perform(
-                            async move { client.submit_simulation(&sim).await.unwrap() },
-                            |_| Message::FetchAllSimulations.into(),
+                            async move {
+                                client.submit_simulation(&sim).await.map_err(|e| {
+                                    let errors = e
+                                        .problem()
+                                        .map(|problem| problem.errors.clone())
+                                        .unwrap_or_default();
+                                    (format!("Failed submitting simulation: {e}"), errors)
+                                })
+                            },
+                            |res| Message::Submitted(file, res).into(),
                         );
                     }
                     Err(e) => {
@@ -275,74 +1007,387 @@ impl Application for AppModel {
                     }
                 }
             }
+            Message::Submitted(file, res) => {
+                self.submitting = false;
+                let submitted = match res {
+                    Ok(submitted) => submitted,
+                    Err((e, validation)) => {
+                        let notify = self.notify(None, "Simulation failed".to_string(), e.clone());
+                        self.has_error = Some(e.clone());
+                        self.validation = validation;
+                        // reruns start from the list, where the editor error isn't visible
+                        return Task::batch([notify, self.toast(e)]);
+                    }
+                };
+
+                let id = submitted.id;
+                self.record_submission(&submitted, file);
+                self.list_submission(id);
+
+                let toast = widget::toaster::Toast::new(format!(
+                    "Simulation finished with {} Wh",
+                    submitted.report.result
+                ))
+                .action("Show".to_string(), move |_| Message::ShowSubmission(id));
+                let notify = self.notify(
+                    Some(id),
+                    "Simulation finished".to_string(),
+                    format!("Result: {} Wh", submitted.report.result),
+                );
+                return Task::batch([
+                    self.toasts.push(toast).map(Into::into),
+                    notify,
+                    self.update(Message::ShowSubmission(id)),
+                ]);
+            }
+            Message::ShowSubmission(id) => {
+                self.activate_page(Page::SimulationList);
+                return self.update(Message::SelectSimulation(id));
+            }
+            Message::ConfirmRowAction(action) => self.confirm = Some(action),
+            Message::CancelRowAction => self.confirm = None,
+            Message::RunRowAction => {
+                let Some(action) = self.confirm.take() else {
+                    return Task::none();
+                };
+                let client = self.client.clone();
+                match action {
+                    RowAction::Rerun(id) => {
+                        if self.submitting {
+                            return Task::none();
+                        }
+                        self.submitting = true;
+                        return Task::perform(
+                            async move {
+                                let source = client
+                                    .fetch_simulation_source(id)
+                                    .await
+                                    .map_err(|e| format!("Failed fetching simulation: {e}"))?;
+                                let sim = serde_json::from_str::<Simulation>(&source)
+                                    .map_err(|e| format!("Invalid simulation source: {e}"))?;
+                                client
+                                    .submit_simulation(&sim)
+                                    .await
+                                    .map_err(|e| format!("Failed submitting simulation: {e}"))
+                            },
+                            // the editor doesn't show this simulation, so neither its errors
+                            |res| Message::Submitted(None, res.map_err(|e| (e, Vec::new()))).into(),
+                        );
+                    }
+                    RowAction::CloneToEditor(id) => {
+                        return Task::perform(
+                            async move {
+                                let source = client
+                                    .fetch_simulation_source(id)
+                                    .await
+                                    .map_err(|e| format!("Failed fetching simulation: {e}"))?;
+                                // pretty printed for editing, as the server may send it minified
+                                let json = serde_json::from_str::<serde_json::Value>(&source)
+                                    .and_then(|json| serde_json::to_string_pretty(&json))
+                                    .unwrap_or(source);
+                                Ok(json)
+                            },
+                            |res| Message::ClonedSource(res).into(),
+                        );
+                    }
+                    RowAction::Delete(id) => {
+                        return Task::perform(
+                            async move {
+                                client
+                                    .delete_simulation(id)
+                                    .await
+                                    .map_err(|e| format!("Failed deleting simulation: {e}"))
+                            },
+                            move |res| Message::Deleted(id, res).into(),
+                        );
+                    }
+                }
+            }
+            Message::Deleted(id, res) => {
+                if let Err(e) = res {
+                    return self.toast(e);
+                }
+                self.simulations.retain(|sim| sim.id != id);
+                self.details.remove(&id);
+                if self.compare_base == Some(id) {
+                    self.compare_base = None;
+                }
+                self.new_simulations.remove(&id);
+                if self.state.metadata.remove(&id).is_some() {
+                    self.save_state();
+                }
+                if self
+                    .selected_simulation
+                    .as_ref()
+                    .is_some_and(|(sel, _)| *sel == id)
+                {
+                    self.selected_simulation = None;
+                    self.detail_request.cancel();
+                    self.text_request.cancel();
+                    self.drop_device_logs();
+                    self.set_text_display(None);
+                }
+                return self.toast("Simulation deleted".to_string());
+            }
+            Message::ClonedSource(res) => match res {
+                Ok(json) => {
+                    self.editor_path = None;
+                    self.set_editor_text(&json);
+                    self.has_error = None;
+                    self.activate_page(Page::NewSimulation);
+                }
+                Err(e) => return self.toast(e),
+            },
+            Message::Notified(id, res) => match (id, res) {
+                (Some(id), Ok(notification)) => {
+                    self.notifications.insert(notification, id);
+                }
+                (_, Ok(_)) => {}
+                (_, Err(e)) => return self.toast(e),
+            },
+            Message::OpenNotification(notification) => {
+                let Some(id) = self.notifications.remove(&notification) else {
+                    // some other application's notification
+                    return Task::none();
+                };
+                let focus = match self.core.main_window_id() {
+                    Some(window) => window::gain_focus(window),
+                    None => Task::none(),
+                };
+                return Task::batch([focus, self.update(Message::ShowSubmission(id))]);
+            }
+            Message::NotificationClosed(notification) => {
+                self.notifications.remove(&notification);
+            }
+            Message::Notifications(enabled) => {
+                self.config.notifications = enabled;
+                self.save_config();
+            }
             Message::NewSimulation => {
-                self.editor_content = widget::text_editor::Content::with_text(DEFAULT_SIMULATION);
+                self.editor_path = None;
+                self.set_editor_text(DEFAULT_SIMULATION);
             }
             Message::FetchAllSimulations => {
+                if self.connect_error.is_some() {
+                    return Task::none();
+                }
                 let client = self.client.clone();
-                return Task::perform(
-                    async move { client.fetch_all_simulations().await.unwrap() },
-                    |mut res| {
-                        res.sort_unstable_by(|a, b| b.timestamp.cmp(&a.timestamp).reverse());
-                        Message::SimulationsFetched(res).into()
-                    },
-                );
+                return self.list_request.start(|generation| {
+                    Task::perform(
+                        async move {
+                            match client.fetch_simulations_page(None, PAGE_SIZE).await {
+                                Ok(page) => Ok(page),
+                                Err(e) if client.offline() => client
+                                    .cached_simulations_page(PAGE_SIZE)
+                                    .ok_or_else(|| format!("Offline and nothing cached: {e}")),
+                                Err(e) => Err(format!("Failed fetching simulations: {e}")),
+                            }
+                        },
+                        move |res| Message::SimulationsFetched(generation, res).into(),
+                    )
+                });
             }
             Message::ReplaceEditorContent(cont) => {
-                self.editor_content = widget::text_editor::Content::with_text(&cont);
+                self.set_editor_text(&cont);
+            }
+            Message::CopyTextToClipboard => return self.update(Message::CopyAs(CopyFormat::Raw)),
+            Message::CopyAs(format) => {
+                if let Some(text) = self.copy_text(format) {
+                    return Task::batch([
+                        cosmic::iced::clipboard::write(text),
+                        self.toast(format!("Copied {}", format.label())),
+                    ]);
+                }
             }
-            Message::CopyTextToClipboard => if let Some(content) = &self.text_display {},
+            Message::CloseToast(id) => self.toasts.remove(id),
             Message::ShowAllDeviceLog(id) => {
                 let client = self.client.clone();
-                return Task::perform(
-                    async move { client.fetch_simulation_log(id).await.unwrap() },
-                    move |text| Message::FetchedText(id, text).into(),
-                );
+                return self.fetch_text(async move {
+                    client
+                        .fetch_simulation_log(id)
+                        .await
+                        .map_err(|e| format!("Failed fetching log: {e}"))
+                });
             }
             Message::ShowSource(id) => {
                 let client = self.client.clone();
-                return Task::perform(
-                    async move {
-                        let ret = client.fetch_simulation_source(id).await.unwrap();
-                        let json: serde_json::Value = serde_json::from_str(&ret).unwrap();
-                        serde_json::to_string_pretty(&json).unwrap()
-                    },
-                    move |text| Message::FetchedText(id, text).into(),
-                );
+                return self.fetch_text(async move {
+                    let source = client
+                        .fetch_simulation_source(id)
+                        .await
+                        .map_err(|e| format!("Failed fetching source: {e}"))?;
+                    // shown as sent if it isn't JSON after all
+                    Ok(serde_json::from_str::<serde_json::Value>(&source)
+                        .ok()
+                        .and_then(|json| serde_json::to_string_pretty(&json).ok())
+                        .unwrap_or(source))
+                });
             }
             Message::ShowDeviceLog(id, ident) => {
+                if let Some(log) = self
+                    .device_logs
+                    .as_ref()
+                    .and_then(|logs| logs.log(id, &ident))
+                {
+                    let log = log.clone();
+                    self.text_request.cancel();
+                    self.set_text_display(Some(log));
+                    return Task::none();
+                }
                 let client = self.client.clone();
-                let ident = ident.clone();
-                return Task::perform(
-                    async move {
-                        client
-                            .fetch_simulation_log_by_device(id, &ident)
-                            .await
-                            .unwrap()
-                    },
-                    move |text| Message::FetchedText(id, text).into(),
-                );
+                return self.fetch_text(async move {
+                    client
+                        .fetch_simulation_log_by_device(id, &ident)
+                        .await
+                        .map_err(|e| format!("Failed fetching log: {e}"))
+                });
             }
-            Message::FetchedText(source_id, text) => {
-                if let Some((id, _)) = self.selected_simulation {
-                    if source_id == id {
-                        self.text_display = Some(text);
+            Message::FetchAllDeviceLogs(id) => {
+                let Some(devices) = self
+                    .selected_simulation
+                    .as_ref()
+                    .filter(|(selected, _)| *selected == id)
+                    .map(|(_, sim)| sim.devices.clone())
+                else {
+                    return Task::none();
+                };
+                self.device_logs = Some(DeviceLogs {
+                    id,
+                    total: devices.len(),
+                    logs: Vec::new(),
+                    errors: Vec::new(),
+                });
+                if devices.is_empty() {
+                    return Task::none();
+                }
+                let client = self.client.clone();
+                return self.logs_request.start(|generation| {
+                    Task::run(
+                        fetch_device_logs(client, id, devices, generation),
+                        Into::into,
+                    )
+                });
+            }
+            Message::DeviceLogFetched(generation, device, res) => {
+                if generation != self.logs_request.generation {
+                    return Task::none();
+                }
+                let Some(logs) = &mut self.device_logs else {
+                    return Task::none();
+                };
+                match res {
+                    Ok(log) => logs.logs.push((device, log)),
+                    Err(e) => logs.errors.push((device, e)),
+                }
+                if logs.done() == logs.total {
+                    self.logs_request.finish(generation);
+                }
+            }
+            Message::PickForComparison(id) => self.compare_base = Some(id),
+            Message::CompareRuns(old, new) => {
+                let client = self.client.clone();
+                let old_label = SimulationMeta::display_name(self.state.metadata.get(&old), old);
+                let new_label = SimulationMeta::display_name(self.state.metadata.get(&new), new);
+                return self.fetch_comparison(async move {
+                    let (old_source, new_source) = futures::future::try_join(
+                        client.fetch_simulation_source(old),
+                        client.fetch_simulation_source(new),
+                    )
+                    .await
+                    .map_err(|e| format!("Failed fetching source: {e}"))?;
+                    Ok(diff::compare(
+                        old_label,
+                        &old_source,
+                        new_label,
+                        &new_source,
+                    ))
+                });
+            }
+            Message::CompareWithDraft(id) => {
+                let client = self.client.clone();
+                let old_label = SimulationMeta::display_name(self.state.metadata.get(&id), id);
+                let new_label = self
+                    .editor_path
+                    .as_deref()
+                    .and_then(Path::file_name)
+                    .map_or_else(|| "Draft".to_string(), |name| name.to_string_lossy().into());
+                let draft = self.editor_content.text();
+                return self.fetch_comparison(async move {
+                    let source = client
+                        .fetch_simulation_source(id)
+                        .await
+                        .map_err(|e| format!("Failed fetching source: {e}"))?;
+                    Ok(diff::compare(old_label, &source, new_label, &draft))
+                });
+            }
+            Message::Compared(generation, res) => {
+                if !self.text_request.finish(generation) {
+                    return Task::none();
+                }
+                match res {
+                    Ok(comparison) => {
+                        self.set_text_display(None);
+                        self.comparison = Some(comparison);
                     }
+                    Err(e) => return self.toast(e),
+                }
+            }
+            Message::UnifiedDiff(unified) => self.unified_diff = unified,
+            Message::CloseComparison => self.comparison = None,
+            Message::FetchedText(generation, res) => {
+                // a slower response to an earlier click
+                if !self.text_request.finish(generation) {
+                    return Task::none();
+                }
+                match res {
+                    Ok(text) => self.set_text_display(Some(text)),
+                    Err(e) => return self.toast(e),
                 }
             }
+            Message::NextSimulation => return self.select_adjacent_simulation(1),
+            Message::PreviousSimulation => return self.select_adjacent_simulation(-1),
             Message::SelectSimulation(id) => {
+                self.new_simulations.remove(&id);
+                if self.device_logs.as_ref().is_some_and(|logs| logs.id != id) {
+                    self.drop_device_logs();
+                }
+                // the log or source shown belongs to the simulation selected before
+                self.text_request.cancel();
+                self.set_text_display(None);
                 let client = self.client.clone();
-                return Task::perform(
-                    async move { client.fetch_simulation(id).await.unwrap() },
-                    move |sim| Message::FetchedSimulation(id, sim).into(),
-                );
+                return self.detail_request.start(|generation| {
+                    Task::perform(
+                        async move {
+                            client
+                                .fetch_simulation(id)
+                                .await
+                                .map_err(|e| format!("Failed fetching simulation: {e}"))
+                        },
+                        move |res| Message::FetchedSimulation(generation, id, res).into(),
+                    )
+                });
             }
-            Message::FetchedSimulation(id, sim) => {
+            Message::FetchedSimulation(generation, id, res) => {
+                if !self.detail_request.finish(generation) {
+                    return Task::none();
+                }
+                let sim = match res {
+                    Ok(sim) => sim,
+                    Err(e) => return self.toast(e),
+                };
+                self.details.insert(id, sim.clone());
                 self.selected_simulation = Some((id, sim));
-                self.text_display = None;
+                self.meta_draft = self.state.metadata.get(&id).cloned().unwrap_or_default();
+                self.meta_tags_input = self.meta_draft.tags.join(", ");
+                self.meta_notes = widget::text_editor::Content::with_text(&self.meta_draft.notes);
+                self.set_text_display(None);
             }
 
-            Message::OpenRepositoryUrl => {}
+            Message::OpenRepositoryUrl => {
+                if let Err(e) = open::that_detached(REPOSITORY) {
+                    return self.toast(format!("Failed opening {REPOSITORY}: {e}"));
+                }
+            }
 
             Message::ToggleContextPage(context_page) => {
                 if self.context_page == context_page {
@@ -359,77 +1404,2909 @@ impl Application for AppModel {
                 return Task::perform(
                     async move {
                         let filter = FileFilter::new("Json").glob("*.json");
-                        let file = file_chooser::open::Dialog::new()
-                            .title("Choose simulation json")
-                            .filter(filter)
-                            .open_file()
-                            .await
-                            .unwrap();
-                        std::fs::read_to_string(file.url().path()).unwrap()
+                        let Some(path) = choose_open_path("Choose simulation json", filter).await?
+                        else {
+                            return Ok(None);
+                        };
+                        let content = read_file(&path)?;
+                        Ok(Some((path, content)))
                     },
-                    |cont| Message::ReplaceEditorContent(cont).into(),
+                    |res| Message::FileOpened(res).into(),
                 )
             }
-            Message::Edit(action) => self.editor_content.perform(action),
-            Message::SimulationsFetched(sims) => {
-                self.simulations = sims;
-                self.nav_model.activate_position(0);
+            Message::OpenRecent(i) => {
+                let Some(path) = self.config.recent_files.get(i).cloned() else {
+                    return Task::none();
+                };
+                return Task::perform(
+                    async move {
+                        let content = read_file(&path)?;
+                        Ok(Some((path, content)))
+                    },
+                    |res| Message::FileOpened(res).into(),
+                );
             }
-        }
-        Task::none()
-    }
-}
-
-impl AppModel {
-    /// The about page for this app.
-    pub fn about(&self) -> Element<Message> {
-        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
-
-        let icon = widget::svg(widget::svg::Handle::from_memory(APP_ICON));
-
-        let title = widget::text::title3("ha-ui");
-
-        let link = widget::button::link(REPOSITORY)
-            .on_press(Message::OpenRepositoryUrl)
-            .padding(0);
-
-        widget::column()
-            .push(icon)
-            .push(title)
-            .push(link)
-            .align_x(Alignment::Center)
-            .spacing(space_xxs)
-            .into()
-    }
-
-    /// Updates the header and window titles.
-    pub fn update_title(&mut self) -> Task<Message> {
-        let window_title = "ha-ui".to_string();
-
-        if let Some(id) = self.core.main_window_id() {
-            self.set_window_title(window_title, id)
-        } else {
-            Task::none()
-        }
-    }
-}
-
-pub enum Page {
-    SimulationList,
-    NewSimulation,
-}
-
-/// The context page to display in the context drawer.
-#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
-pub enum ContextPage {
-    #[default]
-    About,
-}
+            Message::FileOpened(res) => match res {
+                Ok(Some((path, content))) => {
+                    self.set_editor_text(&content);
+                    self.add_recent_file(path.clone());
+                    self.editor_path = Some(path);
+                    self.has_error = None;
+                }
+                Ok(None) => {}
+                Err(e) => self.has_error = Some(e),
+            },
+            Message::Save => return self.save_editor(self.editor_path.clone()),
+            Message::SaveAs => return self.save_editor(None),
+            Message::Saved(res) => match res {
+                Ok(Some(path)) => {
+                    self.add_recent_file(path.clone());
+                    self.editor_path = Some(path);
+                    self.has_error = None;
+                }
+                Ok(None) => {}
+                Err(e) => self.has_error = Some(e),
+            },
+            Message::ImportCsv => {
+                return Task::perform(
+                    async move {
+                        let filter = FileFilter::new("CSV").glob("*.csv");
+                        let Some(path) = choose_open_path("Choose device CSV", filter).await?
+                        else {
+                            return Ok(None);
+                        };
+                        read_file(&path).map(Some)
+                    },
+                    |res| Message::CsvImported(res).into(),
+                );
+            }
+            Message::CsvImported(res) => match res {
+                Ok(Some(csv)) => match simulation::devices_from_csv(&csv) {
+                    Ok(devices) => {
+                        // keep the time window the user is working on, if there is one
+                        let sim = serde_json::from_str::<Simulation>(&self.editor_content.text())
+                            .or_else(|_| serde_json::from_str(DEFAULT_SIMULATION))
+                            .expect("default simulation is valid")
+                            .with_devices(devices);
+                        let json = serde_json::to_string_pretty(&sim)
+                            .expect("simulation serializes to json");
+                        self.set_editor_text(&json);
+                        self.has_error = None;
+                        self.activate_page(Page::NewSimulation);
+                    }
+                    Err(e) => self.has_error = Some(format!("Failed importing CSV: {e}")),
+                },
+                Ok(None) => {}
+                Err(e) => self.has_error = Some(e),
+            },
+            Message::ExportResults => {
+                let Some(overview) = self
+                    .selected_simulation
+                    .as_ref()
+                    .and_then(|(id, _)| self.simulations.iter().find(|sim| sim.id == *id).cloned())
+                else {
+                    return Task::none();
+                };
+                let client = self.client.clone();
+                let format = self.export_format;
+                self.exporting = true;
+                return Task::perform(
+                    async move {
+                        let file_name = format!("{}.{}", overview.id, format.extension());
+                        let Some(path) = choose_save_path("Export simulation", &file_name).await
+                        else {
+                            return Ok(None);
+                        };
+                        export::export_simulation(&client, &overview, format, &path)
+                            .await
+                            .map_err(|e| format!("Export failed: {e:#}"))?;
+                        Ok(Some(path))
+                    },
+                    |res| Message::Exported(res).into(),
+                );
+            }
+            Message::ExportAll => {
+                let client = self.client.clone();
+                let simulations = self.simulations.clone();
+                let format = self.export_format;
+                self.exporting = true;
+                let total = simulations.len();
+                let export = cosmic::iced::stream::channel(16, move |mut output| async move {
+                    use cosmic::iced::futures::SinkExt;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum MenuAction {
-    About,
-    OpenFile,
+                    let res: Result<_, String> = async {
+                        let Some(path) =
+                            choose_save_path("Export all simulations", "simulations.zip").await
+                        else {
+                            return Ok(None);
+                        };
+                        export::export_all(&client, &simulations, format, &path, |done| {
+                            // skipped if the app is behind, the next one catches up
+                            let _ = output.try_send(Message::ExportProgress(done, total));
+                        })
+                        .await
+                        .map_err(|e| format!("Export failed: {e:#}"))?;
+                        Ok(Some(path))
+                    }
+                    .await;
+                    let _ = output.send(Message::Exported(res)).await;
+                });
+                return Task::run(export, Into::into);
+            }
+            Message::GenerateReport => {
+                let Some(overview) = self
+                    .selected_simulation
+                    .as_ref()
+                    .and_then(|(id, _)| self.simulations.iter().find(|sim| sim.id == *id).cloned())
+                else {
+                    return Task::none();
+                };
+                let client = self.client.clone();
+                let template = self.config.report_template.clone();
+                let pdf_command = self.config.pdf_command.clone();
+                self.generating_report = true;
+                return Task::perform(
+                    async move {
+                        // saving under a .pdf name converts the report to PDF
+                        let file_name = format!("{}.html", overview.id);
+                        let Some(path) = choose_save_path("Save report", &file_name).await else {
+                            return Ok(None);
+                        };
+                        report::generate(
+                            &client,
+                            &overview,
+                            template.as_deref(),
+                            pdf_command.as_deref(),
+                            &path,
+                        )
+                        .await
+                        .map_err(|e| format!("Generating report failed: {e:#}"))?;
+                        Ok(Some(path))
+                    },
+                    |res| Message::ReportGenerated(res).into(),
+                );
+            }
+            Message::ReportGenerated(res) => {
+                self.generating_report = false;
+                let message = match res {
+                    Ok(Some(path)) => format!("Report saved to {}", path.display()),
+                    Ok(None) => return Task::none(),
+                    Err(e) => e,
+                };
+                return self.toast(message);
+            }
+            Message::ChooseReportTemplate => {
+                return Task::perform(
+                    async move {
+                        let filter = FileFilter::new("HTML").glob("*.html").glob("*.htm");
+                        choose_open_path("Choose report template", filter).await
+                    },
+                    |res| Message::ReportTemplateChosen(res).into(),
+                );
+            }
+            Message::ReportTemplateChosen(res) => match res {
+                Ok(Some(path)) => {
+                    self.config.report_template = Some(path);
+                    self.save_config();
+                }
+                Ok(None) => {}
+                Err(e) => return self.toast(e),
+            },
+            Message::ResetReportTemplate => {
+                self.config.report_template = None;
+                self.save_config();
+            }
+            Message::PdfCommandInput(command) => self.pdf_command_input = Some(command),
+            Message::PdfCommandSubmit => {
+                let Some(command) = self.pdf_command_input.take() else {
+                    return Task::none();
+                };
+                self.config.pdf_command = Some(command).filter(|c| !c.trim().is_empty());
+                self.save_config();
+            }
+            Message::ExportFormatSelected(i) => {
+                if let Some(format) = ExportFormat::ALL.get(i) {
+                    self.export_format = *format;
+                }
+            }
+            Message::ExportProgress(done, total) => self.export_progress = Some((done, total)),
+            Message::Exported(res) => {
+                self.exporting = false;
+                self.export_progress = None;
+                let message = match res {
+                    Ok(Some(path)) => format!("Exported to {}", path.display()),
+                    Ok(None) => return Task::none(),
+                    Err(e) => e,
+                };
+                return self.toast(message);
+            }
+            Message::Quit => {
+                if let Some(id) = self.core.main_window_id() {
+                    return cosmic::iced::window::close(id);
+                }
+            }
+            Message::Undo => {
+                if let Some(text) = self.editor_history.undo(self.editor_content.text()) {
+                    self.editor_content = widget::text_editor::Content::with_text(&text);
+                    self.editor_typing = false;
+                }
+            }
+            Message::Redo => {
+                if let Some(text) = self.editor_history.redo(self.editor_content.text()) {
+                    self.editor_content = widget::text_editor::Content::with_text(&text);
+                    self.editor_typing = false;
+                }
+            }
+            Message::FormatJson => {
+                match serde_json::from_str::<serde_json::Value>(&self.editor_content.text()) {
+                    Ok(json) => {
+                        let pretty =
+                            serde_json::to_string_pretty(&json).expect("json value serializes");
+                        self.set_editor_text(&pretty);
+                        self.has_error = None;
+                    }
+                    Err(e) => self.has_error = Some(format!("Failed parsing json: {e}")),
+                }
+            }
+            Message::Edit(action) => {
+                if let widget::text_editor::Action::Edit(edit) = &action {
+                    let typing = matches!(
+                        edit,
+                        widget::text_editor::Edit::Insert(c) if !c.is_whitespace()
+                    );
+                    self.editor_history
+                        .record(self.editor_content.text(), typing && self.editor_typing);
+                    self.editor_typing = typing;
+                }
+                self.editor_content.perform(action);
+            }
+            Message::SimulationsFetched(generation, res) => {
+                // from before switching servers or fetching again
+                if !self.list_request.finish(generation) {
+                    return Task::none();
+                }
+                let page = match res {
+                    Ok(page) => page,
+                    Err(e) => return self.toast(e),
+                };
+                self.simulations = page.simulations;
+                self.next_page = page.next;
+                return Task::batch([
+                    self.fetch_missing_details(),
+                    self.update(Message::FlushQueue),
+                ]);
+            }
+            Message::LoadMoreSimulations => {
+                let Some(cursor) = self.next_page.clone() else {
+                    return Task::none();
+                };
+                if self.loading_more {
+                    return Task::none();
+                }
+                self.loading_more = true;
+                let client = self.client.clone();
+                let generation = self.list_request.generation;
+                return Task::perform(
+                    async move {
+                        client
+                            .fetch_simulations_page(Some(&cursor), PAGE_SIZE)
+                            .await
+                            .map_err(|e| format!("Failed loading more simulations: {e}"))
+                    },
+                    move |res| Message::MoreSimulationsFetched(generation, res).into(),
+                );
+            }
+            Message::MoreSimulationsFetched(generation, res) => {
+                self.loading_more = false;
+                if generation != self.list_request.generation {
+                    return Task::none();
+                }
+                match res {
+                    Ok(page) => {
+                        for sim in page.simulations {
+                            if !self.simulations.iter().any(|known| known.id == sim.id) {
+                                self.simulations.push(sim);
+                            }
+                        }
+                        self.next_page = page.next;
+                        return self.fetch_missing_details();
+                    }
+                    Err(e) => return self.toast(e),
+                }
+            }
+            Message::WindowFocused(focused) => self.focused = focused,
+            Message::AutoRefresh => {
+                if self.refreshing || self.connect_error.is_some() {
+                    return Task::none();
+                }
+                self.refreshing = true;
+                let client = self.client.clone();
+                let generation = self.list_request.generation;
+                return Task::perform(
+                    async move {
+                        client
+                            .fetch_simulations_page(None, PAGE_SIZE)
+                            .await
+                            .map_err(|e| e.to_string())
+                    },
+                    move |res| Message::AutoRefreshed(generation, res).into(),
+                );
+            }
+            Message::AutoRefreshed(generation, res) => {
+                self.refreshing = false;
+                if generation != self.list_request.generation {
+                    return Task::none();
+                }
+                match res {
+                    Ok(page) => {
+                        self.refresh_failures = 0;
+                        let complete = page.next.is_none();
+                        let added =
+                            refresh::merge(&mut self.simulations, page.simulations, complete);
+                        self.new_simulations.extend(added);
+                        self.new_simulations
+                            .retain(|id| self.simulations.iter().any(|sim| sim.id == *id));
+                        return Task::batch([
+                            self.fetch_missing_details(),
+                            self.update(Message::FlushQueue),
+                        ]);
+                    }
+                    Err(e) => {
+                        // polling continues with growing pauses until the server is back
+                        self.refresh_failures = (self.refresh_failures + 1).min(16);
+                        // once, the status line tells about the failures that follow
+                        if self.refresh_failures == 1 {
+                            return self.toast(format!("Failed refreshing simulations: {e}"));
+                        }
+                    }
+                }
+            }
+            Message::RefreshIntervalSelected(i) => {
+                if let Some(interval) = refresh::INTERVALS.get(i) {
+                    self.config.refresh_interval = *interval;
+                    self.refresh_failures = 0;
+                    self.save_config();
+                }
+            }
+            Message::WatchSimulation(id) => {
+                self.live = Some(LiveRun::new(id));
+                self.live_charts.clear();
+                self.activate_page(Page::LiveRun);
+            }
+            Message::LiveEvent(id, event) => {
+                let Some(live) = self.live.as_mut().filter(|live| live.id == id) else {
+                    return Task::none();
+                };
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        live.outcome = Some(Err(e));
+                        return Task::none();
+                    }
+                };
+                let running = live.outcome.is_none();
+                let finished = matches!(event, api::SimulationEvent::Finished(_));
+                if live.apply(event) {
+                    for (device, table) in live.charts_to_draw(Instant::now()) {
+                        let Some(svg) = report::chart(table) else {
+                            continue;
+                        };
+                        let chart = widget::svg::Handle::from_memory(svg.into_bytes());
+                        match self
+                            .live_charts
+                            .iter_mut()
+                            .find(|(name, _)| name == device.as_str())
+                        {
+                            Some((_, drawn)) => *drawn = chart,
+                            None => self.live_charts.push((device.as_str().to_string(), chart)),
+                        }
+                    }
+                }
+                let failed = running && matches!(live.outcome, Some(Err(_)));
+                if finished || failed {
+                    let message = live.describe();
+                    let summary = if finished {
+                        "Simulation finished"
+                    } else {
+                        "Simulation failed"
+                    };
+                    return Task::batch([
+                        self.toast(message.clone()),
+                        self.notify(Some(id), summary.to_string(), message),
+                        self.update(Message::AutoRefresh),
+                    ]);
+                }
+            }
+            Message::LiveEnded(id) => {
+                if let Some(live) = self.live.as_mut().filter(|live| live.id == id) {
+                    if live.outcome.is_none() {
+                        live.outcome = Some(Err("The server closed the event stream".to_string()));
+                    }
+                }
+            }
+            Message::StopWatching => {
+                self.live = None;
+                self.live_charts.clear();
+            }
+            Message::ListScrolled(viewport) => {
+                self.list_viewport = Some(viewport);
+                let rows = self.list_rows().len();
+                let (offset, height) = list_window(Some(viewport));
+                if virtual_list::near_end(offset, height, rows) {
+                    return Task::batch([
+                        self.update(Message::LoadMoreSimulations),
+                        self.fetch_missing_details(),
+                    ]);
+                }
+                return self.fetch_missing_details();
+            }
+            Message::DetailsFetched(id, sim) => {
+                // Reports requested from a previous server are no longer pending.
+                if !self.details_pending.remove(&id) {
+                    return Task::none();
+                }
+                if let Some(sim) = sim {
+                    self.details.insert(id, sim);
+                }
+            }
+            Message::ListQuery(query) => {
+                self.list_filter.query = query;
+                return self.fetch_missing_details();
+            }
+            Message::ListFrom(input) => {
+                self.list_filter.from = input.trim().parse().ok();
+                self.list_from_input = input;
+                return self.fetch_missing_details();
+            }
+            Message::ListTo(input) => {
+                self.list_filter.to = input.trim().parse().ok();
+                self.list_to_input = input;
+                return self.fetch_missing_details();
+            }
+            Message::ListSortSelected(i) => {
+                if let Some(sort) = SortOrder::ALL.get(i) {
+                    self.list_filter.sort = *sort;
+                }
+                return self.fetch_missing_details();
+            }
+            Message::ListGroupByDay(group) => {
+                self.list_filter.group_by_day = group;
+                return self.fetch_missing_details();
+            }
+            Message::MetaName(name) => self.meta_draft.name = name,
+            Message::MetaTags(input) => self.meta_tags_input = input,
+            Message::MetaNotes(action) => self.meta_notes.perform(action),
+            Message::MetaSave => {
+                let Some((id, _)) = &self.selected_simulation else {
+                    return Task::none();
+                };
+                let id = *id;
+                let meta = SimulationMeta {
+                    name: self.meta_draft.name.trim().to_string(),
+                    tags: metadata::parse_tags(&self.meta_tags_input),
+                    notes: self.meta_notes.text().trim_end().to_string(),
+                };
+                self.meta_tags_input = meta.tags.join(", ");
+                if meta.is_empty() {
+                    self.state.metadata.remove(&id);
+                } else {
+                    self.state.metadata.insert(id, meta.clone());
+                }
+                self.meta_draft = meta.clone();
+                self.save_state();
+
+                if self.config.sync_metadata {
+                    let client = self.client.clone();
+                    return Task::perform(
+                        async move {
+                            client.update_metadata(id, &meta).await.map_err(|e| {
+                                use reqwest::StatusCode;
+                                match e.status() {
+                                    Some(
+                                        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED,
+                                    ) => "Server doesn't store metadata, saved locally".to_string(),
+                                    _ => format!("Failed sending metadata: {e}"),
+                                }
+                            })
+                        },
+                        |res| Message::MetaSynced(res).into(),
+                    );
+                }
+            }
+            Message::MetaSynced(res) => {
+                if let Err(e) = res {
+                    return self.toast(e);
+                }
+            }
+            Message::SyncMetadata(sync) => {
+                self.config.sync_metadata = sync;
+                self.save_config();
+            }
+            Message::Key(modifiers, key) => {
+                if self.palette.is_some() {
+                    return self.palette_key(&key);
+                }
+                let action = self
+                    .key_binds
+                    .iter()
+                    .find(|(key_bind, _)| key_bind.matches(modifiers, &key))
+                    .map(|(_, action)| *action);
+                if let Some(action) = action {
+                    return self.update(menu::action::MenuAction::message(&action));
+                }
+            }
+            Message::UpdateConfig(mut config) => {
+                self.key_binds = key_binds::key_binds(&config.key_binds);
+                if config.profiles.is_empty() {
+                    config.profiles.push(ServerProfile::default());
+                }
+                self.config = config;
+                self.update_profile_names();
+                self.update_client();
+            }
+            Message::ConnectTimeoutSelected(i) => {
+                if let Some(timeout) = CONNECT_TIMEOUTS.get(i) {
+                    self.config.connect_timeout = *timeout;
+                    self.save_config();
+                    self.update_client();
+                }
+            }
+            Message::RequestTimeoutSelected(i) => {
+                if let Some(timeout) = REQUEST_TIMEOUTS.get(i) {
+                    self.config.request_timeout = *timeout;
+                    self.save_config();
+                    self.update_client();
+                }
+            }
+            Message::RetriesSelected(i) => {
+                if let Some(retries) = RETRIES.get(i) {
+                    self.config.retries = *retries;
+                    self.save_config();
+                    self.update_client();
+                }
+            }
+            Message::IdempotentSubmit(enabled) => {
+                self.config.idempotent_submit = enabled;
+                self.save_config();
+                self.update_client();
+            }
+            Message::FlushQueue => {
+                if self.flushing || self.client.offline() || self.connect_error.is_some() {
+                    return Task::none();
+                }
+                let server = self.client.auth.server();
+                let Some(queued) = self
+                    .state
+                    .queued
+                    .iter()
+                    .find(|q| q.server == server)
+                    .cloned()
+                else {
+                    return Task::none();
+                };
+                let sim = match serde_json::from_str::<Simulation>(&queued.simulation) {
+                    Ok(sim) => sim,
+                    Err(e) => {
+                        self.state.queued.retain(|q| *q != queued);
+                        self.save_state();
+                        return self.toast(format!("Dropped invalid queued simulation: {e}"));
+                    }
+                };
+                self.flushing = true;
+                let client = self.client.clone();
+                return Task::perform(
+                    async move {
+                        match client.submit_simulation(&sim).await {
+                            // other failures may happen after the server got the simulation,
+                            // sending it again could run it twice
+                            Err(api::Error::Connect(e)) if e.is_connect() => {
+                                Message::QueuedUnsent(queued)
+                            }
+                            res => Message::QueuedSubmitted(
+                                queued,
+                                res.map_err(|e| {
+                                    format!("Failed submitting queued simulation: {e}")
+                                }),
+                            ),
+                        }
+                    },
+                    Into::into,
+                );
+            }
+            Message::QueuedUnsent(_) => {
+                // stays queued until the server is back
+                self.flushing = false;
+            }
+            Message::QueuedSubmitted(queued, res) => {
+                self.flushing = false;
+                self.state.queued.retain(|q| *q != queued);
+                self.save_state();
+                let toast = match res {
+                    Ok(submitted) => {
+                        let id = submitted.id;
+                        self.record_submission(&submitted, queued.file);
+                        // the server may have been switched in the meantime
+                        if queued.server == self.client.auth.server() {
+                            self.list_submission(id);
+                        }
+                        let toast = widget::toaster::Toast::new(format!(
+                            "Queued simulation finished with {} Wh",
+                            submitted.report.result
+                        ))
+                        .action("Show".to_string(), move |_| Message::ShowSubmission(id));
+                        self.toasts.push(toast).map(Into::into)
+                    }
+                    Err(e) => self.toast(e),
+                };
+                return Task::batch([toast, self.update(Message::FlushQueue)]);
+            }
+            Message::DiskCache(enabled) => {
+                self.config.disk_cache = enabled;
+                self.save_config();
+                self.client
+                    .cache
+                    .set_dir(enabled.then(cache::default_dir).flatten());
+            }
+            Message::ClearCache => {
+                let cache = self.client.cache.clone();
+                return Task::perform(
+                    async move {
+                        cache
+                            .clear()
+                            .map_err(|e| format!("Failed clearing the cache: {e:#}"))
+                    },
+                    |res| Message::CacheCleared(res).into(),
+                );
+            }
+            Message::CacheCleared(res) => {
+                return self.toast(match res {
+                    Ok(()) => "Cache cleared".to_string(),
+                    Err(e) => e,
+                });
+            }
+            Message::SwitchProfile(i) => {
+                if i >= self.config.profiles.len() {
+                    return Task::none();
+                }
+                self.config.active_profile = i;
+                self.save_config();
+                return self.connect();
+            }
+            Message::AddProfile => {
+                self.config.profiles.push(ServerProfile {
+                    name: format!("Server {}", self.config.profiles.len() + 1),
+                    ..ServerProfile::default()
+                });
+                self.save_config();
+                self.update_profile_names();
+            }
+            Message::RemoveProfile(i) => {
+                if self.config.profiles.len() < 2 || i >= self.config.profiles.len() {
+                    return Task::none();
+                }
+                let active = self.config.profile_index();
+                self.config.profiles.remove(i);
+                self.update_profile_names();
+                if i < active {
+                    self.config.active_profile = active - 1;
+                } else if i == active {
+                    self.config.active_profile = 0;
+                    self.save_config();
+                    return self.connect();
+                }
+                self.save_config();
+            }
+            Message::ProfileName(i, name) => {
+                if let Some(profile) = self.config.profiles.get_mut(i) {
+                    profile.name = name;
+                    self.save_config();
+                    self.update_profile_names();
+                    return self.update_title();
+                }
+            }
+            Message::ProfileEndpoint(i, endpoint) => {
+                // takes effect when connecting to the profile
+                if let Some(profile) = self.config.profiles.get_mut(i) {
+                    profile.endpoint = endpoint;
+                    self.save_config();
+                }
+            }
+            Message::AuthMethodSelected(i) => {
+                let Some(method) = AuthMethod::from_index(i) else {
+                    return Task::none();
+                };
+                if method.index() == self.config.profile().auth.index() {
+                    return Task::none();
+                }
+                self.config.profile_mut().auth = method;
+                self.auth_secret.clear();
+                self.save_config();
+                // credentials of the old method are useless now
+                if self.has_credentials {
+                    return self.update(Message::Logout);
+                }
+            }
+            Message::AuthUsername(name) => {
+                if let AuthMethod::Basic { username } = &mut self.config.profile_mut().auth {
+                    *username = name;
+                    self.save_config();
+                }
+            }
+            Message::AuthSecretInput(secret) => self.auth_secret = secret,
+            Message::OAuthIssuer(issuer) => {
+                if let AuthMethod::OAuth(settings) = &mut self.config.profile_mut().auth {
+                    settings.issuer = issuer;
+                    self.save_config();
+                }
+            }
+            Message::OAuthClientId(client_id) => {
+                if let AuthMethod::OAuth(settings) = &mut self.config.profile_mut().auth {
+                    settings.client_id = client_id;
+                    self.save_config();
+                }
+            }
+            Message::OAuthScope(scope) => {
+                if let AuthMethod::OAuth(settings) = &mut self.config.profile_mut().auth {
+                    settings.scope = scope;
+                    self.save_config();
+                }
+            }
+            Message::OAuthFlowSelected(i) => {
+                if let (AuthMethod::OAuth(settings), Some(flow)) =
+                    (&mut self.config.profile_mut().auth, OAuthFlow::ALL.get(i))
+                {
+                    settings.flow = *flow;
+                    self.save_config();
+                }
+            }
+            Message::SaveCredentials => {
+                if self.connect_error.is_some() {
+                    return Task::none();
+                }
+                let credentials = match &self.config.profile().auth {
+                    AuthMethod::Bearer => auth::Credentials::Bearer {
+                        token: self.auth_secret.trim().to_string(),
+                    },
+                    AuthMethod::Basic { username } => auth::Credentials::Basic {
+                        username: username.clone(),
+                        password: self.auth_secret.clone(),
+                    },
+                    AuthMethod::None | AuthMethod::OAuth(_) => return Task::none(),
+                };
+                if self.auth_secret.trim().is_empty() {
+                    return Task::none();
+                }
+                self.auth_secret.clear();
+                let server = self.client.auth.server().to_string();
+                return Task::perform(
+                    async move {
+                        let res = auth::store(&server, &credentials)
+                            .await
+                            .map(|()| credentials)
+                            .map_err(|e| format!("Failed storing credentials: {e:#}"));
+                        (server, res)
+                    },
+                    |(server, res)| Message::CredentialsStored(server, res).into(),
+                );
+            }
+            Message::SecretsLoaded(server, res) => {
+                if server != self.client.auth.server() || self.connect_error.is_some() {
+                    return Task::none();
+                }
+                let toast = match res {
+                    Ok((credentials, password)) => {
+                        self.has_credentials = credentials.is_some();
+                        self.client.auth.set(credentials);
+                        self.certificate_password = Some(password.unwrap_or_default());
+                        if self.config.profile().tls.is_pkcs12() {
+                            self.apply_tls()
+                        } else {
+                            Task::none()
+                        }
+                    }
+                    Err(e) => self.toast(e),
+                };
+                return Task::batch([toast, self.update(Message::FetchAllSimulations)]);
+            }
+            Message::CredentialsStored(server, res) => {
+                self.logging_in = false;
+                self.device_login = None;
+                // logins finishing after switching servers are stored, but not used now
+                if server != self.client.auth.server() || self.connect_error.is_some() {
+                    return Task::none();
+                }
+                match res {
+                    Ok(credentials) => {
+                        self.client.auth.set(Some(credentials));
+                        self.has_credentials = true;
+                        return Task::batch([
+                            self.toast("Signed in".to_string()),
+                            self.update(Message::FetchAllSimulations),
+                        ]);
+                    }
+                    Err(e) => return self.toast(e),
+                }
+            }
+            Message::Login => {
+                if self.connect_error.is_some() {
+                    return Task::none();
+                }
+                let AuthMethod::OAuth(settings) = self.config.profile().auth.clone() else {
+                    return Task::none();
+                };
+                if self.logging_in {
+                    return Task::none();
+                }
+                self.logging_in = true;
+                let http = self.client.client.clone();
+                let server = self.client.auth.server().to_string();
+                return match settings.flow {
+                    OAuthFlow::DeviceCode => Task::perform(
+                        async move {
+                            auth::start_device_login(&http, &settings)
+                                .await
+                                .map_err(|e| format!("Login failed: {e:#}"))
+                        },
+                        |res| Message::DeviceLoginStarted(res).into(),
+                    ),
+                    OAuthFlow::Pkce => Task::perform(
+                        async move {
+                            let res = async {
+                                let credentials = auth::browser_login(&http, &settings).await?;
+                                auth::store(&server, &credentials).await?;
+                                Ok(credentials)
+                            };
+                            let res = res
+                                .await
+                                .map_err(|e: eyre::Report| format!("Login failed: {e:#}"));
+                            (server, res)
+                        },
+                        |(server, res)| Message::CredentialsStored(server, res).into(),
+                    ),
+                };
+            }
+            Message::DeviceLoginStarted(res) => {
+                let login = match res {
+                    Ok(login) => login,
+                    Err(e) => {
+                        self.logging_in = false;
+                        return self.toast(e);
+                    }
+                };
+                self.device_login = Some(login.clone());
+                let http = self.client.client.clone();
+                let server = self.client.auth.server().to_string();
+                return Task::perform(
+                    async move {
+                        let res = async {
+                            let credentials = auth::finish_device_login(&http, login).await?;
+                            auth::store(&server, &credentials).await?;
+                            Ok(credentials)
+                        };
+                        let res = res
+                            .await
+                            .map_err(|e: eyre::Report| format!("Login failed: {e:#}"));
+                        (server, res)
+                    },
+                    |(server, res)| Message::CredentialsStored(server, res).into(),
+                );
+            }
+            Message::OpenVerificationPage => {
+                if let Some(login) = &self.device_login {
+                    let url = login
+                        .verification_uri_complete
+                        .as_ref()
+                        .unwrap_or(&login.verification_uri);
+                    if let Err(e) = open::that_detached(url) {
+                        return self.toast(format!("Failed opening {url}: {e}"));
+                    }
+                }
+            }
+            Message::Logout => {
+                self.client.auth.set(None);
+                self.has_credentials = false;
+                let server = self.client.auth.server().to_string();
+                return Task::perform(
+                    async move {
+                        auth::delete(&server)
+                            .await
+                            .map_err(|e| format!("Failed removing credentials: {e:#}"))
+                    },
+                    |res| Message::LoggedOut(res).into(),
+                );
+            }
+            Message::LoggedOut(res) => {
+                if let Err(e) = res {
+                    return self.toast(e);
+                }
+            }
+            Message::ChooseTlsFile(file) => {
+                return Task::perform(
+                    async move { choose_open_path(file.title(), file.filter()).await },
+                    move |res| Message::TlsFileChosen(file, res).into(),
+                );
+            }
+            Message::TlsFileChosen(file, res) => match res {
+                Ok(Some(path)) => {
+                    *file.path_mut(&mut self.config.profile_mut().tls) = Some(path);
+                    self.save_config();
+                    return self.apply_tls();
+                }
+                Ok(None) => {}
+                Err(e) => return self.toast(e),
+            },
+            Message::ResetTlsFile(file) => {
+                *file.path_mut(&mut self.config.profile_mut().tls) = None;
+                self.save_config();
+                return self.apply_tls();
+            }
+            Message::AcceptInvalidCerts(accept) => {
+                self.config.profile_mut().tls.accept_invalid_certs = accept;
+                self.save_config();
+                return self.apply_tls();
+            }
+            Message::CertificatePasswordInput(password) => {
+                self.certificate_password_input = password;
+            }
+            Message::SaveCertificatePassword => {
+                let password = std::mem::take(&mut self.certificate_password_input);
+                let server = self.client.auth.server().to_string();
+                return Task::perform(
+                    async move {
+                        let res = auth::store_certificate_password(&server, &password)
+                            .await
+                            .map(|()| password)
+                            .map_err(|e| format!("Failed storing password: {e:#}"));
+                        (server, res)
+                    },
+                    |(server, res)| Message::CertificatePasswordStored(server, res).into(),
+                );
+            }
+            Message::CertificatePasswordStored(server, res) => {
+                if server != self.client.auth.server() || self.connect_error.is_some() {
+                    return Task::none();
+                }
+                match res {
+                    Ok(password) => {
+                        self.certificate_password = Some(password);
+                        return self.apply_tls();
+                    }
+                    Err(e) => return self.toast(e),
+                }
+            }
+            Message::KeyBindInput(action, input) => {
+                self.key_bind_inputs.insert(action, input);
+            }
+            Message::KeyBindSubmit(action) => {
+                let Some(input) = self.key_bind_inputs.remove(&action) else {
+                    return Task::none();
+                };
+                let input = input.trim();
+                if !input.is_empty() {
+                    match key_binds::parse(input) {
+                        Ok(key_bind) => {
+                            self.config
+                                .key_binds
+                                .insert(action.id().to_string(), key_binds::format(&key_bind));
+                        }
+                        Err(e) => {
+                            self.key_bind_error = Some(format!("{}: {e}", action.label()));
+                            return Task::none();
+                        }
+                    }
+                } else {
+                    self.config
+                        .key_binds
+                        .insert(action.id().to_string(), String::new());
+                }
+                self.key_bind_error = None;
+                self.key_binds = key_binds::key_binds(&self.config.key_binds);
+                self.save_config();
+            }
+            Message::KeyBindReset(action) => {
+                self.key_bind_inputs.remove(&action);
+                self.config.key_binds.remove(action.id());
+                self.key_bind_error = None;
+                self.key_binds = key_binds::key_binds(&self.config.key_binds);
+                self.save_config();
+            }
+            Message::TogglePalette => {
+                if self.palette.take().is_none() {
+                    self.palette = Some(CommandPalette::default());
+                    return widget::text_input::focus(self.palette_input.clone());
+                }
+            }
+            Message::PaletteInput(query) => {
+                if let Some(palette) = &mut self.palette {
+                    palette.query = query;
+                    palette.selected = 0;
+                }
+            }
+            Message::PaletteSubmit => {
+                let entry = self.palette.as_ref().and_then(|palette| {
+                    self.palette_entries(&palette.query)
+                        .into_iter()
+                        .nth(palette.selected)
+                });
+                if let Some(entry) = entry {
+                    return self.update(Message::PaletteActivate(entry));
+                }
+            }
+            Message::PaletteActivate(entry) => {
+                self.palette = None;
+                match entry {
+                    PaletteEntry::Action(action) => {
+                        return self.update(menu::action::MenuAction::message(&action));
+                    }
+                    PaletteEntry::Simulation(id) => {
+                        self.activate_page(Page::SimulationList);
+                        return self.update(Message::SelectSimulation(id));
+                    }
+                }
+            }
+        }
+        Task::none()
+    }
+
+    fn toast(&mut self, message: String) -> Task<Message> {
+        self.toasts
+            .push(widget::toaster::Toast::new(message))
+            .map(Into::into)
+    }
+
+    /// Shows a desktop notification while the window is in the background.
+    ///
+    /// With an `id` the notification can open that simulation.
+    fn notify(&self, id: Option<Uuid>, summary: String, body: String) -> Task<Message> {
+        if self.focused || !self.config.notifications {
+            return Task::none();
+        }
+        Task::perform(
+            async move {
+                notification::notify(Self::APP_ID, &summary, &body, id.is_some())
+                    .await
+                    .map_err(|e| format!("Failed showing notification: {e}"))
+            },
+            move |res| Message::Notified(id, res).into(),
+        )
+    }
+
+    /// Stops fetching the logs of each device and forgets those fetched.
+    fn drop_device_logs(&mut self) {
+        self.logs_request.cancel();
+        self.device_logs = None;
+    }
+
+    /// Fills the text panel with the comparison `fetch` returns, like [`Self::fetch_text`].
+    fn fetch_comparison(
+        &mut self,
+        fetch: impl Future<Output = Result<Comparison, String>> + Send + 'static,
+    ) -> Task<Message> {
+        self.text_request.start(|generation| {
+            Task::perform(fetch, move |res| Message::Compared(generation, res).into())
+        })
+    }
+
+    /// Two simulations side by side, or as a unified diff if chosen or if either is invalid.
+    fn comparison_view<'a>(&'a self, comparison: &'a Comparison) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let unified = self.unified_diff || comparison.rows.is_none();
+        let controls = widget::row()
+            .push(
+                widget::text::heading(format!(
+                    "{} → {}",
+                    comparison.old_label, comparison.new_label
+                ))
+                .width(Length::Fill),
+            )
+            .push_maybe(comparison.rows.is_some().then(|| {
+                widget::button::text(if unified { "Side by side" } else { "Unified" })
+                    .on_press(Message::UnifiedDiff(!unified))
+            }))
+            .push(
+                widget::button::icon(widget::icon::from_name("window-close-symbolic"))
+                    .on_press(Message::CloseComparison),
+            )
+            .align_y(Alignment::Center)
+            .spacing(space_xxs);
+
+        let lines: Vec<Element<_>> = match &comparison.rows {
+            _ if comparison.same => vec![widget::text("No differences").into()],
+            Some(rows) if !unified => rows
+                .iter()
+                .map(|row| {
+                    widget::row()
+                        .push(
+                            widget::container(diff_text(&row.old, row.kind))
+                                .width(Length::FillPortion(1)),
+                        )
+                        .push(
+                            widget::container(diff_text(&row.new, row.kind))
+                                .width(Length::FillPortion(1)),
+                        )
+                        .spacing(space_xxs)
+                        .into()
+                })
+                .collect(),
+            _ => comparison
+                .unified
+                .lines()
+                .map(|line| {
+                    let kind = if line.starts_with("---") || line.starts_with("+++") {
+                        RowKind::Same
+                    } else if line.starts_with('+') {
+                        RowKind::Added
+                    } else if line.starts_with('-') {
+                        RowKind::Removed
+                    } else if line.starts_with("@@") {
+                        RowKind::Changed
+                    } else {
+                        RowKind::Same
+                    };
+                    diff_text(line, kind).into()
+                })
+                .collect(),
+        };
+
+        widget::column()
+            .push(controls)
+            .push(widget::scrollable(widget::column::with_children(lines)).width(Length::Fill))
+            .spacing(space_xxs)
+            .into()
+    }
+
+    fn set_text_display(&mut self, text: Option<ParsedText>) {
+        self.comparison = None;
+        let (text, json, table) = match text {
+            Some(ParsedText { text, json, table }) => (Some(text), json, table),
+            None => (None, None, None),
+        };
+        self.output_json = json;
+        self.output_table = table;
+        self.text_display = text;
+    }
+
+    fn can_copy_as(&self, format: CopyFormat) -> bool {
+        match format {
+            CopyFormat::Raw => self.text_display.is_some(),
+            CopyFormat::PrettyJson => self.output_json.is_some(),
+            CopyFormat::Csv => self.output_table.is_some(),
+            CopyFormat::Markdown => self.selected_simulation.is_some(),
+        }
+    }
+
+    /// The output panel content in the requested format.
+    fn copy_text(&self, format: CopyFormat) -> Option<String> {
+        match format {
+            CopyFormat::Raw => self.text_display.clone(),
+            CopyFormat::PrettyJson => self
+                .output_json
+                .as_ref()
+                .map(|json| serde_json::to_string_pretty(json).expect("json value serializes")),
+            CopyFormat::Csv => self.output_table.as_ref().map(Table::to_csv),
+            CopyFormat::Markdown => {
+                let (id, sim) = self.selected_simulation.as_ref()?;
+                let mut report = Table::new(vec!["Field".into(), "Value".into()]);
+                report.rows.push(vec!["Simulation".into(), id.to_string()]);
+                if let Some(overview) = self.simulations.iter().find(|s| s.id == *id) {
+                    report
+                        .rows
+                        .push(vec!["Timestamp".into(), overview.timestamp.to_string()]);
+                }
+                report
+                    .rows
+                    .push(vec!["Result".into(), format!("{} Wh", sim.res.result)]);
+                report.rows.push(vec![
+                    "Devices".into(),
+                    sim.devices
+                        .iter()
+                        .map(DeviceName::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ]);
+
+                let mut markdown = report.to_markdown();
+                if let Some(log) = &self.output_table {
+                    markdown.push('\n');
+                    markdown.push_str(&log.to_markdown());
+                }
+                Some(markdown)
+            }
+        }
+    }
+
+    /// Replaces the editor content, keeping the old content undoable.
+    fn set_editor_text(&mut self, text: &str) {
+        self.editor_history
+            .record(self.editor_content.text(), false);
+        self.editor_typing = false;
+        self.editor_content = widget::text_editor::Content::with_text(text);
+        self.validation.clear();
+    }
+
+    /// Writes the editor content to `path`, or asks where to write it if there is none.
+    fn save_editor(&self, path: Option<PathBuf>) -> Task<Message> {
+        let text = self.editor_content.text();
+        Task::perform(
+            async move {
+                let path = match path {
+                    Some(path) => path,
+                    None => match choose_save_path("Save simulation json", "simulation.json").await
+                    {
+                        Some(path) => path,
+                        None => return Ok(None),
+                    },
+                };
+                write_file(&path, text)?;
+                Ok(Some(path))
+            },
+            |res| Message::Saved(res).into(),
+        )
+    }
+
+    fn add_recent_file(&mut self, path: PathBuf) {
+        self.config.recent_files.retain(|p| *p != path);
+        self.config.recent_files.insert(0, path);
+        self.config.recent_files.truncate(MAX_RECENT_FILES);
+        self.save_config();
+    }
+
+    fn activate_page(&mut self, page: Page) {
+        let id = self
+            .nav_model
+            .iter()
+            .find(|id| self.nav_model.data::<Page>(*id) == Some(&page));
+        if let Some(id) = id {
+            self.nav_model.activate(id);
+        }
+    }
+
+    /// The about page for this app.
+    pub fn about(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let icon = widget::svg(widget::svg::Handle::from_memory(APP_ICON));
+
+        let title = widget::text::title3("ha-ui");
+
+        let version = widget::text::caption(concat!("Version ", env!("CARGO_PKG_VERSION")));
+
+        let link = widget::button::link(REPOSITORY)
+            .on_press(Message::OpenRepositoryUrl)
+            .padding(0);
+
+        widget::column()
+            .push(icon)
+            .push(title)
+            .push(version)
+            .push(link)
+            .align_x(Alignment::Center)
+            .spacing(space_xxs)
+            .into()
+    }
+
+    /// The settings page for this app.
+    pub fn settings(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut section = widget::settings::section().title("Keyboard shortcuts");
+        for action in MenuAction::ALL {
+            let value = match self.key_bind_inputs.get(&action) {
+                Some(input) => input.as_str(),
+                None => key_binds::binding(action, &self.config.key_binds).unwrap_or_default(),
+            };
+            section = section.add(widget::settings::item(
+                action.label(),
+                widget::row()
+                    .push(
+                        widget::text_input("Unbound", value)
+                            .on_input(move |input| Message::KeyBindInput(action, input))
+                            .on_submit(Message::KeyBindSubmit(action))
+                            .width(Length::Fixed(160.0)),
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-undo-symbolic"))
+                            .on_press(Message::KeyBindReset(action)),
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_xxs),
+            ));
+        }
+
+        let template = match &self.config.report_template {
+            Some(path) => path.display().to_string(),
+            None => "Built-in".to_string(),
+        };
+        let reports = widget::settings::section()
+            .title("Reports")
+            .add(widget::settings::item(
+                "Template",
+                widget::row()
+                    .push(widget::text(template))
+                    .push(widget::button::text("Choose…").on_press(Message::ChooseReportTemplate))
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-undo-symbolic"))
+                            .on_press(Message::ResetReportTemplate),
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_xxs),
+            ))
+            .add(widget::settings::item(
+                "PDF converter",
+                widget::text_input(
+                    report::DEFAULT_PDF_COMMAND,
+                    self.pdf_command_input
+                        .as_deref()
+                        .or(self.config.pdf_command.as_deref())
+                        .unwrap_or_default(),
+                )
+                .on_input(Message::PdfCommandInput)
+                .on_submit(Message::PdfCommandSubmit)
+                .on_unfocus(Message::PdfCommandSubmit)
+                .width(Length::Fixed(240.0)),
+            ));
+
+        widget::column()
+            .push_maybe(
+                self.key_bind_error
+                    .as_ref()
+                    .map(|err| widget::text::text(err)),
+            )
+            .push(section)
+            .push(reports)
+            .push(
+                widget::settings::section()
+                    .title("Server")
+                    .add(
+                        widget::settings::item::builder("Send names, tags and notes to the server")
+                            .toggler(self.config.sync_metadata, Message::SyncMetadata),
+                    )
+                    .add(
+                        widget::settings::item::builder("Notify when simulations end")
+                            .description("Only while the window is in the background")
+                            .toggler(self.config.notifications, Message::Notifications),
+                    )
+                    .add(widget::settings::item(
+                        "Check for new simulations",
+                        widget::dropdown(
+                            &refresh::INTERVAL_LABELS,
+                            refresh::INTERVALS
+                                .iter()
+                                .position(|i| *i == self.config.refresh_interval),
+                            Message::RefreshIntervalSelected,
+                        ),
+                    )),
+            )
+            .push(
+                widget::settings::section()
+                    .title("Requests")
+                    .add(widget::settings::item(
+                        "Connection timeout",
+                        widget::dropdown(
+                            &CONNECT_TIMEOUT_LABELS,
+                            CONNECT_TIMEOUTS
+                                .iter()
+                                .position(|t| *t == self.config.connect_timeout),
+                            Message::ConnectTimeoutSelected,
+                        ),
+                    ))
+                    .add(widget::settings::item(
+                        "Request timeout",
+                        widget::dropdown(
+                            &REQUEST_TIMEOUT_LABELS,
+                            REQUEST_TIMEOUTS
+                                .iter()
+                                .position(|t| *t == self.config.request_timeout),
+                            Message::RequestTimeoutSelected,
+                        ),
+                    ))
+                    .add(widget::settings::item(
+                        "Retries of failed requests",
+                        widget::dropdown(
+                            &RETRY_LABELS,
+                            RETRIES.iter().position(|r| *r == self.config.retries),
+                            Message::RetriesSelected,
+                        ),
+                    ))
+                    .add(
+                        widget::settings::item::builder("Retry submissions")
+                            .description(
+                                "Sends an Idempotency-Key header, the server must support it",
+                            )
+                            .toggler(self.config.idempotent_submit, Message::IdempotentSubmit),
+                    )
+                    .add(
+                        widget::settings::item::builder("Cache on disk")
+                            .description(
+                                "Keeps finished simulations in the cache directory across restarts",
+                            )
+                            .toggler(self.config.disk_cache, Message::DiskCache),
+                    )
+                    .add(widget::settings::item(
+                        "Cached responses",
+                        widget::button::text("Clear cache").on_press(Message::ClearCache),
+                    )),
+            )
+            .push(self.profile_settings())
+            .push(self.auth_settings())
+            .push(self.tls_settings())
+            .push_maybe(self.args.overrides().then(|| {
+                widget::text::caption("Some request settings are overridden on the command line")
+            }))
+            .spacing(space_xxs)
+            .into()
+    }
+
+    fn profile_settings(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let removable = self.config.profiles.len() > 1;
+        let mut section = widget::settings::section().title("Servers");
+        for (i, profile) in self.config.profiles.iter().enumerate() {
+            section = section.add(
+                widget::row()
+                    .push(
+                        widget::text_input("Name", &profile.name)
+                            .on_input(move |name| Message::ProfileName(i, name))
+                            .width(Length::FillPortion(1)),
+                    )
+                    .push(
+                        widget::text_input("http://localhost:8000", &profile.endpoint)
+                            .on_input(move |endpoint| Message::ProfileEndpoint(i, endpoint))
+                            .on_submit(Message::SwitchProfile(i))
+                            .width(Length::FillPortion(2)),
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-delete-symbolic"))
+                            .on_press_maybe(removable.then_some(Message::RemoveProfile(i))),
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_xxs),
+            );
+        }
+        section
+            .add(
+                widget::row()
+                    .push(widget::text::caption(
+                        "Press Enter in a URL to connect, authentication applies to the active server",
+                    ))
+                    .push(widget::horizontal_space())
+                    .push(widget::button::text("Add server").on_press(Message::AddProfile))
+                    .align_y(Alignment::Center),
+            )
+            .into()
+    }
+
+    fn auth_settings(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let secret_input = |placeholder| {
+            widget::row()
+                .push(
+                    widget::secure_input(placeholder, &self.auth_secret, None, true)
+                        .on_input(Message::AuthSecretInput)
+                        .on_submit(Message::SaveCredentials)
+                        .width(Length::Fixed(200.0)),
+                )
+                .push(widget::button::text("Save").on_press_maybe(
+                    (!self.auth_secret.trim().is_empty()).then_some(Message::SaveCredentials),
+                ))
+                .align_y(Alignment::Center)
+                .spacing(space_xxs)
+        };
+
+        let mut section = widget::settings::section().title("Authentication").add(
+            widget::settings::item::builder("Method")
+                .description("Changing the method signs out")
+                .control(widget::dropdown(
+                    &AuthMethod::LABELS,
+                    Some(self.config.profile().auth.index()),
+                    Message::AuthMethodSelected,
+                )),
+        );
+        match &self.config.profile().auth {
+            AuthMethod::None => {}
+            AuthMethod::Bearer => {
+                section = section.add(widget::settings::item("Token", secret_input("Token")));
+            }
+            AuthMethod::Basic { username } => {
+                section = section
+                    .add(widget::settings::item(
+                        "Username",
+                        widget::text_input("Username", username)
+                            .on_input(Message::AuthUsername)
+                            .width(Length::Fixed(200.0)),
+                    ))
+                    .add(widget::settings::item("Password", secret_input("Password")));
+            }
+            AuthMethod::OAuth(settings) => {
+                section = section
+                    .add(widget::settings::item(
+                        "Issuer",
+                        widget::text_input("https://auth.example.com", &settings.issuer)
+                            .on_input(Message::OAuthIssuer)
+                            .width(Length::Fixed(240.0)),
+                    ))
+                    .add(widget::settings::item(
+                        "Client ID",
+                        widget::text_input("", &settings.client_id)
+                            .on_input(Message::OAuthClientId)
+                            .width(Length::Fixed(240.0)),
+                    ))
+                    .add(widget::settings::item(
+                        "Scopes",
+                        widget::text_input("Separated by spaces", &settings.scope)
+                            .on_input(Message::OAuthScope)
+                            .width(Length::Fixed(240.0)),
+                    ))
+                    .add(widget::settings::item(
+                        "Login with",
+                        widget::dropdown(
+                            &OAuthFlow::LABELS,
+                            OAuthFlow::ALL.iter().position(|f| *f == settings.flow),
+                            Message::OAuthFlowSelected,
+                        ),
+                    ))
+                    .add(widget::settings::item(
+                        "Account",
+                        widget::button::text(if self.logging_in {
+                            "Logging in…"
+                        } else {
+                            "Log in"
+                        })
+                        .on_press_maybe(
+                            (!self.logging_in
+                                && !settings.issuer.is_empty()
+                                && !settings.client_id.is_empty())
+                            .then_some(Message::Login),
+                        ),
+                    ));
+                if let Some(login) = &self.device_login {
+                    section = section.add(widget::settings::item(
+                        format!(
+                            "Enter the code {} at {}",
+                            login.user_code, login.verification_uri
+                        ),
+                        widget::button::text("Open page").on_press(Message::OpenVerificationPage),
+                    ));
+                }
+            }
+        }
+        if self.has_credentials {
+            section = section.add(widget::settings::item(
+                "Signed in, the credentials are kept in the keyring",
+                widget::button::text("Sign out").on_press(Message::Logout),
+            ));
+        }
+        section.into()
+    }
+
+    fn tls_settings(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let options = &self.config.profile().tls;
+        let file_item = |file: TlsFile, path: &Option<PathBuf>| {
+            let name = match path {
+                Some(path) => path.display().to_string(),
+                None => "None".to_string(),
+            };
+            widget::settings::item(
+                file.label(),
+                widget::row()
+                    .push(widget::text(name))
+                    .push(widget::button::text("Choose…").on_press(Message::ChooseTlsFile(file)))
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-undo-symbolic"))
+                            .on_press_maybe(path.is_some().then_some(Message::ResetTlsFile(file))),
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_xxs),
+            )
+        };
+
+        let mut section = widget::settings::section()
+            .title("Certificates")
+            .add(file_item(TlsFile::CaBundle, &options.ca_bundle))
+            .add(file_item(
+                TlsFile::ClientCertificate,
+                &options.client_certificate,
+            ));
+        if options.is_pkcs12() {
+            section = section.add(widget::settings::item(
+                "Certificate password",
+                widget::row()
+                    .push(
+                        widget::secure_input(
+                            "Password",
+                            &self.certificate_password_input,
+                            None,
+                            true,
+                        )
+                        .on_input(Message::CertificatePasswordInput)
+                        .on_submit(Message::SaveCertificatePassword)
+                        .width(Length::Fixed(200.0)),
+                    )
+                    .push(widget::button::text("Save").on_press(Message::SaveCertificatePassword))
+                    .align_y(Alignment::Center)
+                    .spacing(space_xxs),
+            ));
+        } else if options.client_certificate.is_some() {
+            section = section.add(file_item(TlsFile::ClientKey, &options.client_key));
+        }
+        section
+            .add(
+                widget::settings::item::builder("Accept invalid certificates")
+                    .description(
+                        "Danger: anyone on the network can read and change what is sent, \
+                         only use this for development servers",
+                    )
+                    .toggler(options.accept_invalid_certs, Message::AcceptInvalidCerts),
+            )
+            .into()
+    }
+
+    /// Every action and simulation matching the palette query, best match first.
+    fn palette_entries(&self, query: &str) -> Vec<PaletteEntry> {
+        let entries = MenuAction::ALL
+            .into_iter()
+            .filter(|action| *action != MenuAction::CommandPalette)
+            .map(|action| (PaletteEntry::Action(action), action.label().to_string()))
+            .chain(self.simulations.iter().map(|sim| {
+                let label = match self.state.metadata.get(&sim.id) {
+                    Some(meta) if !meta.name.is_empty() => format!("{} {}", meta.name, sim.id),
+                    _ => sim.id.to_string(),
+                };
+                (PaletteEntry::Simulation(sim.id), label)
+            }))
+            .collect::<Vec<_>>();
+
+        palette::filter(
+            query,
+            entries
+                .iter()
+                .map(|(entry, label)| (entry.clone(), label.as_str())),
+        )
+    }
+
+    fn palette_view(&self, palette: &CommandPalette) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let entries = self
+            .palette_entries(&palette.query)
+            .into_iter()
+            .take(PALETTE_ENTRIES)
+            .enumerate()
+            .map(|(i, entry)| {
+                let (label, detail) = match &entry {
+                    PaletteEntry::Action(action) => (
+                        action.label().to_string(),
+                        key_binds::binding(*action, &self.config.key_binds)
+                            .unwrap_or_default()
+                            .to_string(),
+                    ),
+                    PaletteEntry::Simulation(id) => (
+                        SimulationMeta::display_name(self.state.metadata.get(id), *id),
+                        self.simulations
+                            .iter()
+                            .find(|sim| sim.id == *id)
+                            .map(|sim| sim.timestamp.to_string())
+                            .unwrap_or_default(),
+                    ),
+                };
+                widget::button::custom(
+                    widget::row()
+                        .push(widget::text(label).width(Length::Fill))
+                        .push(widget::text::caption(detail)),
+                )
+                .class(if i == palette.selected {
+                    theme::Button::Suggested
+                } else {
+                    theme::Button::MenuItem
+                })
+                .on_press(Message::PaletteActivate(entry))
+                .width(Length::Fill)
+                .into()
+            })
+            .collect::<Vec<_>>();
+
+        widget::dialog()
+            .title("Command palette")
+            .control(
+                widget::column()
+                    .push(
+                        widget::search_input("Search actions and simulations", &palette.query)
+                            .id(self.palette_input.clone())
+                            .on_input(Message::PaletteInput)
+                            .on_submit(Message::PaletteSubmit),
+                    )
+                    .push(widget::column::with_children(entries))
+                    .spacing(space_xxs),
+            )
+            .secondary_action(widget::button::standard("Close").on_press(Message::TogglePalette))
+            .into()
+    }
+
+    /// Moves the palette selection with the arrow keys.
+    fn palette_key(&mut self, key: &Key) -> Task<Message> {
+        let Some(palette) = &self.palette else {
+            return Task::none();
+        };
+        let len = self
+            .palette_entries(&palette.query)
+            .len()
+            .min(PALETTE_ENTRIES);
+        let selected = palette.selected;
+
+        let selected = match key {
+            Key::Named(Named::ArrowDown) if selected + 1 < len => selected + 1,
+            Key::Named(Named::ArrowUp) => selected.saturating_sub(1),
+            Key::Named(Named::Escape) => {
+                self.palette = None;
+                return Task::none();
+            }
+            _ => selected,
+        };
+        if let Some(palette) = &mut self.palette {
+            palette.selected = selected;
+        }
+        Task::none()
+    }
+
+    /// Selects the simulation `offset` positions away from the current one in the list.
+    fn select_adjacent_simulation(&mut self, offset: isize) -> Task<Message> {
+        let visible = self.visible_simulations();
+        let current = self
+            .selected_simulation
+            .as_ref()
+            .and_then(|(id, _)| visible.iter().position(|sim| sim == id));
+        let next = match current {
+            Some(i) => i.checked_add_signed(offset),
+            None => Some(0),
+        };
+
+        match next.and_then(|i| visible.get(i)) {
+            Some(id) => {
+                let id = *id;
+                self.activate_page(Page::SimulationList);
+                self.update(Message::SelectSimulation(id))
+            }
+            None => Task::none(),
+        }
+    }
+
+    /// Fills the text panel with what `fetch` returns, unless another request replaced it.
+    fn fetch_text(
+        &mut self,
+        fetch: impl Future<Output = Result<String, String>> + Send + 'static,
+    ) -> Task<Message> {
+        self.text_request.start(|generation| {
+            // parsed here rather than when shown, as logs can be large
+            Task::perform(
+                async move { fetch.await.map(ParsedText::parse) },
+                move |res| Message::FetchedText(generation, res).into(),
+            )
+        })
+    }
+
+    /// Adds a simulation submitted from here to the history.
+    fn record_submission(&mut self, submitted: &api::SubmittedSimulation, file: Option<PathBuf>) {
+        self.state.submissions.insert(
+            0,
+            Submission {
+                id: submitted.id,
+                timestamp: jiff::Timestamp::now(),
+                result: submitted.report.result,
+                file,
+            },
+        );
+        self.state.submissions.truncate(MAX_SUBMISSIONS);
+        self.save_state();
+    }
+
+    /// Shows a simulation just submitted in the list, before the list is fetched again.
+    fn list_submission(&mut self, id: Uuid) {
+        if !self.simulations.iter().any(|sim| sim.id == id) {
+            self.simulations.push(api::SimulationOverview {
+                id,
+                timestamp: jiff::Timestamp::now(),
+            });
+        }
+    }
+
+    /// How often the list is polled, also while offline with polling turned off.
+    fn refresh_base(&self) -> Duration {
+        match self.config.refresh_interval {
+            0 => OFFLINE_RETRY,
+            seconds => Duration::from_secs(seconds.into()),
+        }
+    }
+
+    /// Fetches the reports of listed simulations in the background, so the list can be
+    /// searched and sorted by result.
+    ///
+    /// Only the rows on screen are fetched unless the filter needs every report.
+    fn fetch_missing_details(&mut self) -> Task<Message> {
+        use cosmic::iced::futures::{stream, StreamExt};
+
+        let wanted = if self.list_filter.needs_details() {
+            self.simulations
+                .iter()
+                .map(|sim| sim.id)
+                .collect::<Vec<_>>()
+        } else {
+            let rows = self.list_rows();
+            let (offset, height) = list_window(self.list_viewport);
+            let range = virtual_list::visible_range(offset, height, rows.len());
+            rows[range]
+                .iter()
+                .filter_map(|row| match row {
+                    ListRow::Simulation(sim) => Some(sim.id),
+                    ListRow::Day(_) => None,
+                })
+                .collect()
+        };
+        let missing = wanted
+            .into_iter()
+            .filter(|id| !self.details.contains_key(id) && !self.details_pending.contains(id))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Task::none();
+        }
+        self.details_pending.extend(missing.iter().copied());
+
+        let client = self.client.clone();
+        let fetched = stream::iter(missing)
+            .map(move |id| {
+                let client = client.clone();
+                async move { (id, client.fetch_simulation(id).await.ok()) }
+            })
+            .buffer_unordered(DETAIL_CONCURRENCY);
+        Task::run(fetched, |(id, sim)| Message::DetailsFetched(id, sim).into())
+    }
+
+    /// The rows of the simulation list after filtering, with day headings if grouped.
+    fn list_rows(&self) -> Vec<ListRow<'_>> {
+        self.list_filter
+            .apply(
+                &self.simulations,
+                &self.details,
+                &self.state.metadata,
+                &self.time_zone,
+            )
+            .into_iter()
+            .flat_map(|(day, sims)| {
+                day.map(ListRow::Day)
+                    .into_iter()
+                    .chain(sims.into_iter().map(ListRow::Simulation))
+            })
+            .collect()
+    }
+
+    /// Ids of the simulations in the list, in display order.
+    fn visible_simulations(&self) -> Vec<Uuid> {
+        self.list_rows()
+            .into_iter()
+            .filter_map(|row| match row {
+                ListRow::Simulation(sim) => Some(sim.id),
+                ListRow::Day(_) => None,
+            })
+            .collect()
+    }
+
+    fn confirm_view(&self, action: RowAction) -> Element<Message> {
+        let name = SimulationMeta::display_name(self.state.metadata.get(&action.id()), action.id());
+        let (title, body, confirm) = match action {
+            RowAction::Rerun(_) => (
+                "Rerun simulation?",
+                format!("{name} will be submitted again as a new simulation."),
+                widget::button::suggested("Rerun"),
+            ),
+            RowAction::CloneToEditor(_) => (
+                "Clone to editor?",
+                format!("The editor content will be replaced by {name}. This can be undone."),
+                widget::button::suggested("Clone"),
+            ),
+            RowAction::Delete(_) => (
+                "Delete simulation?",
+                format!(
+                    "{name} and its logs will be deleted from the server. This can't be undone."
+                ),
+                widget::button::destructive("Delete"),
+            ),
+        };
+        widget::dialog()
+            .title(title)
+            .body(body)
+            .primary_action(confirm.on_press(Message::RunRowAction))
+            .secondary_action(widget::button::standard("Cancel").on_press(Message::CancelRowAction))
+            .into()
+    }
+
+    /// Recent submissions with their results, newest first.
+    fn submission_history(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let entries = self.state.submissions.iter().map(|submission| {
+            let source = submission
+                .file
+                .as_ref()
+                .and_then(|path| path.file_name())
+                .map_or_else(
+                    || "Unsaved".to_string(),
+                    |name| name.to_string_lossy().into(),
+                );
+            widget::button::custom(
+                widget::column()
+                    .push(widget::text(format!("{} Wh", submission.result)))
+                    .push(widget::text::caption(format!(
+                        "{source} · {}",
+                        submission
+                            .timestamp
+                            .to_zoned(self.time_zone.clone())
+                            .strftime("%Y-%m-%d %H:%M")
+                    ))),
+            )
+            .on_press(Message::ShowSubmission(submission.id))
+            .width(Length::Fill)
+            .into()
+        });
+
+        widget::column()
+            .push(widget::text::heading("Submissions"))
+            .push_maybe(
+                self.state
+                    .submissions
+                    .is_empty()
+                    .then(|| widget::text::caption("Nothing submitted yet")),
+            )
+            .push(widget::scrollable(
+                widget::column::with_children(entries.collect::<Vec<_>>()).spacing(space_xxs),
+            ))
+            .spacing(space_xxs)
+            .into()
+    }
+
+    fn live_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing {
+            space_xxs, space_s, ..
+        } = theme::active().cosmic().spacing;
+
+        let Some(live) = &self.live else {
+            return widget::text("Choose \"Watch live\" on a simulation to follow its progress")
+                .into();
+        };
+
+        let charts = self.live_charts.iter().map(|(device, chart)| {
+            widget::column()
+                .push(widget::text::heading(device.as_str()))
+                .push(widget::svg(chart.clone()).width(Length::Fill))
+                .spacing(space_xxs)
+                .into()
+        });
+        // the newest lines of every device
+        let lines = live
+            .logs
+            .iter()
+            .flat_map(|log| {
+                let skip = log.lines.len().saturating_sub(LIVE_LOG_LINES);
+                log.lines.iter().skip(skip).map(move |line| {
+                    widget::text::monotext(format!("{}: {line}", log.device.as_str())).into()
+                })
+            })
+            .collect::<Vec<Element<_>>>();
+
+        widget::column()
+            .push(widget::text::title4(SimulationMeta::display_name(
+                self.state.metadata.get(&live.id),
+                live.id,
+            )))
+            .push(widget::text(live.describe()))
+            .push_maybe(
+                live.progress()
+                    .map(|progress| cosmic::iced::widget::progress_bar(0.0..=1.0, progress)),
+            )
+            .push(widget::button::text("Stop watching").on_press(Message::StopWatching))
+            .push(
+                widget::scrollable(
+                    widget::column::with_children(charts.collect::<Vec<Element<_>>>())
+                        .push(widget::column::with_children(lines))
+                        .spacing(space_s),
+                )
+                .height(Length::Fill),
+            )
+            .spacing(space_s)
+            .into()
+    }
+
+    fn metadata_editor(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        widget::column()
+            .push(widget::text_input("Name", &self.meta_draft.name).on_input(Message::MetaName))
+            .push(
+                widget::text_input("Tags, separated by commas", &self.meta_tags_input)
+                    .on_input(Message::MetaTags)
+                    .on_submit(Message::MetaSave),
+            )
+            .push(
+                widget::text_editor(&self.meta_notes)
+                    .placeholder("Notes")
+                    .on_action(Message::MetaNotes)
+                    .height(Length::Fixed(80.0)),
+            )
+            .push(widget::button::text("Save details").on_press(Message::MetaSave))
+            .spacing(space_xxs)
+            .into()
+    }
+
+    fn simulation_list(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+        let filter = &self.list_filter;
+
+        let controls = widget::column()
+            .push(
+                widget::search_input("Search ids and devices", &filter.query)
+                    .on_input(Message::ListQuery)
+                    .on_clear(Message::ListQuery(String::new())),
+            )
+            .push(
+                widget::row()
+                    .push(
+                        widget::text_input("From (YYYY-MM-DD)", &self.list_from_input)
+                            .on_input(Message::ListFrom),
+                    )
+                    .push(
+                        widget::text_input("To (YYYY-MM-DD)", &self.list_to_input)
+                            .on_input(Message::ListTo),
+                    )
+                    .spacing(space_xxs),
+            )
+            .push(
+                widget::row()
+                    .push(widget::dropdown(
+                        &self.sort_labels,
+                        SortOrder::ALL.iter().position(|s| *s == filter.sort),
+                        Message::ListSortSelected,
+                    ))
+                    .push(
+                        widget::checkbox("Group by day", filter.group_by_day)
+                            .on_toggle(Message::ListGroupByDay),
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_xxs),
+            )
+            .spacing(space_xxs);
+
+        // only rows in view are built, spacers stand in for the others
+        let rows = self.list_rows();
+        let (offset, height) = list_window(self.list_viewport);
+        let range = virtual_list::visible_range(offset, height, rows.len());
+        let mut list = widget::column().push(widget::Space::with_height(Length::Fixed(
+            range.start as f32 * ROW_HEIGHT,
+        )));
+        for row in &rows[range.clone()] {
+            let row: Element<_> = match row {
+                ListRow::Day(day) => widget::container(widget::text::heading(day.to_string()))
+                    .center_y(Length::Fixed(ROW_HEIGHT))
+                    .into(),
+                ListRow::Simulation(sim) => {
+                    let meta = self.state.metadata.get(&sim.id);
+                    let mut detail = sim.timestamp.to_string();
+                    if let Some(details) = self.details.get(&sim.id) {
+                        detail.push_str(&format!(" · {} Wh", details.res.result));
+                    }
+                    if let Some(meta) = meta.filter(|meta| !meta.tags.is_empty()) {
+                        detail.push_str(&format!(" · {}", meta.tags.join(", ")));
+                    }
+                    let button = widget::button::custom(
+                        widget::column()
+                            .push(widget::text(SimulationMeta::display_name(meta, sim.id)))
+                            .push(widget::text::caption(detail)),
+                    )
+                    .on_press(Message::SelectSimulation(sim.id))
+                    .width(Length::Fill)
+                    .height(Length::Fixed(ROW_HEIGHT));
+                    let button = if self.new_simulations.contains(&sim.id) {
+                        button.class(theme::Button::Suggested)
+                    } else {
+                        button
+                    };
+                    let rerun = if self.submitting {
+                        menu::Item::ButtonDisabled("Rerun", None, RowAction::Rerun(sim.id))
+                    } else {
+                        menu::Item::Button("Rerun", None, RowAction::Rerun(sim.id))
+                    };
+                    widget::context_menu(
+                        button,
+                        Some(menu::items(
+                            &HashMap::new(),
+                            vec![
+                                rerun,
+                                menu::Item::Button(
+                                    "Clone to editor",
+                                    None,
+                                    RowAction::CloneToEditor(sim.id),
+                                ),
+                                menu::Item::Divider,
+                                menu::Item::Button("Delete…", None, RowAction::Delete(sim.id)),
+                            ],
+                        )),
+                    )
+                    .into()
+                }
+            };
+            list = list.push(row);
+        }
+        list = list
+            .push(widget::Space::with_height(Length::Fixed(
+                (rows.len() - range.end) as f32 * ROW_HEIGHT,
+            )))
+            .push_maybe(self.next_page.is_some().then(|| {
+                widget::button::text(if self.loading_more {
+                    "Loading…"
+                } else {
+                    "Load more"
+                })
+                .on_press_maybe((!self.loading_more).then_some(Message::LoadMoreSimulations))
+            }));
+
+        let unreachable = (self.refresh_failures > 0 || self.client.offline()).then(|| {
+            let retry = refresh::interval(self.refresh_base(), self.refresh_failures);
+            widget::text::caption(format!(
+                "Server unreachable, trying again in {} s",
+                retry.as_secs()
+            ))
+        });
+
+        let not_connected = self.connect_error.as_ref().map(|e| {
+            widget::text::caption(format!(
+                "Not connected to {}: {e}",
+                self.config.profile().name
+            ))
+        });
+
+        widget::column()
+            .push(controls)
+            .push_maybe(not_connected)
+            .push_maybe(unreachable)
+            .push_maybe(self.export_progress.map(|(done, total)| {
+                widget::column()
+                    .push(cosmic::iced::widget::progress_bar(
+                        0.0..=total as f32,
+                        done as f32,
+                    ))
+                    .push(widget::text::caption(format!(
+                        "Exporting, fetched {done} of {total} simulations"
+                    )))
+            }))
+            .push_maybe(
+                self.list_request
+                    .loading()
+                    .then(|| loading("Loading simulations…")),
+            )
+            .push(
+                widget::scrollable(list)
+                    .on_scroll(Message::ListScrolled)
+                    .height(Length::Fill),
+            )
+            .spacing(space_xxs)
+            .into()
+    }
+
+    /// Connects to the server of the active profile, dropping what came from the last one.
+    fn connect(&mut self) -> Task<Message> {
+        self.simulations.clear();
+        self.details.clear();
+        self.details_pending.clear();
+        self.new_simulations.clear();
+        self.next_page = None;
+        self.selected_simulation = None;
+        self.list_request.cancel();
+        self.detail_request.cancel();
+        self.text_request.cancel();
+        self.drop_device_logs();
+        self.set_text_display(None);
+        self.compare_base = None;
+        self.live = None;
+        self.live_charts.clear();
+        self.refresh_failures = 0;
+        self.has_credentials = false;
+        self.logging_in = false;
+        self.device_login = None;
+        self.certificate_password = None;
+        self.certificate_password_input.clear();
+        self.update_profile_names();
+        self.connect_error = None;
+
+        let profile = self.config.profile().clone();
+        let endpoint = match Url::parse(&profile.endpoint) {
+            Ok(endpoint) => endpoint,
+            Err(e) => return self.connect_failed(format!("Invalid URL: {e}")),
+        };
+        // a PKCS#12 certificate is added once its password is loaded with the credentials
+        let tls = match tls::load(&profile.tls, None) {
+            Ok(tls) => tls,
+            Err(e) => return self.connect_failed(format!("Failed loading certificates: {e:#}")),
+        };
+        let session = Arc::new(auth::Session::new(endpoint.to_string()));
+        let policy = request_policy(&self.config, &self.args);
+        match api::Client::new(endpoint, policy, session, &tls, self.client.cache.clone()) {
+            Ok(client) => self.client = Arc::new(client),
+            Err(e) => return self.connect_failed(e.to_string()),
+        }
+
+        // the list is fetched once the credentials are known
+        let server = self.client.auth.server().to_string();
+        let load = Task::perform(
+            async move {
+                let res = async {
+                    let credentials = auth::load(&server).await?;
+                    let password = auth::load_certificate_password(&server).await?;
+                    Ok((credentials, password))
+                };
+                let res = res
+                    .await
+                    .map_err(|e: eyre::Report| format!("Failed loading credentials: {e:#}"));
+                (server, res)
+            },
+            |(server, res)| Message::SecretsLoaded(server, res).into(),
+        );
+        let warning = if profile.tls.accept_invalid_certs {
+            self.toast(format!(
+                "Certificates of {} aren't verified, anyone on the network can read the traffic",
+                profile.name
+            ))
+        } else {
+            Task::none()
+        };
+        Task::batch([load, warning, self.update_title()])
+    }
+
+    /// Leaves the active profile unconnected, so nothing goes to the previous server.
+    fn connect_failed(&mut self, error: String) -> Task<Message> {
+        let toast = self.toast(format!(
+            "Failed connecting to {}: {error}",
+            self.config.profile().name
+        ));
+        self.connect_error = Some(error);
+        Task::batch([toast, self.update_title()])
+    }
+
+    fn update_profile_names(&mut self) {
+        self.profile_names = self
+            .config
+            .profiles
+            .iter()
+            .map(|p| p.name.clone())
+            .collect();
+    }
+
+    /// Replaces the client if the request settings changed.
+    fn update_client(&mut self) {
+        let policy = request_policy(&self.config, &self.args);
+        if policy == self.client.policy {
+            return;
+        }
+        match self.new_client(policy) {
+            Ok(client) => self.client = Arc::new(client),
+            Err(e) => self
+                .failures
+                .push(format!("Failed applying request settings: {e}")),
+        }
+    }
+
+    /// Replaces the client after the certificates of the profile changed.
+    fn apply_tls(&mut self) -> Task<Message> {
+        if self.connect_error.is_some() {
+            return self.connect();
+        }
+        match self.new_client(self.client.policy) {
+            Ok(client) => {
+                self.client = Arc::new(client);
+                Task::none()
+            }
+            Err(e) => self.toast(e),
+        }
+    }
+
+    /// A client for the current server and session with the profile's certificates.
+    fn new_client(&self, policy: api::RequestPolicy) -> Result<api::Client, String> {
+        let tls = tls::load(
+            &self.config.profile().tls,
+            self.certificate_password.as_deref(),
+        )
+        .map_err(|e| format!("Failed loading certificates: {e:#}"))?;
+        api::Client::new(
+            self.client.endpoint.clone(),
+            policy,
+            self.client.auth.clone(),
+            &tls,
+            self.client.cache.clone(),
+        )
+        .map(|client| {
+            client.set_offline(self.client.offline());
+            client
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    fn save_config(&mut self) {
+        if let Some(handler) = &self.config_handler {
+            if let Err(e) = self.config.write_entry(handler) {
+                self.failures.push(format!("Failed saving settings: {e}"));
+            }
+        }
+    }
+
+    fn save_state(&mut self) {
+        if let Some(handler) = &self.state_handler {
+            if let Err(e) = self.state.write_entry(handler) {
+                self.failures.push(format!("Failed saving app state: {e}"));
+            }
+        }
+    }
+
+    /// Updates the header and window titles.
+    pub fn update_title(&mut self) -> Task<Message> {
+        let mut window_title = format!("ha-ui — {}", self.config.profile().name);
+        if self.connect_error.is_some() {
+            window_title.push_str(" (not connected)");
+        }
+
+        if let Some(id) = self.core.main_window_id() {
+            self.set_window_title(window_title, id)
+        } else {
+            Task::none()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Page {
+    SimulationList,
+    NewSimulation,
+    LiveRun,
+}
+
+/// A menu button which is greyed out unless `enabled`.
+fn menu_button<L>(label: L, action: MenuAction, enabled: bool) -> menu::Item<MenuAction, L>
+where
+    L: Into<std::borrow::Cow<'static, str>>,
+{
+    if enabled {
+        menu::Item::Button(label, None, action)
+    } else {
+        menu::Item::ButtonDisabled(label, None, action)
+    }
+}
+
+/// How many reports [`AppModel::fetch_missing_details`] requests at once.
+const DETAIL_CONCURRENCY: usize = 4;
+
+/// How many submissions the editor page remembers.
+const MAX_SUBMISSIONS: usize = 50;
+
+/// How often the server is checked while offline if polling is turned off.
+const OFFLINE_RETRY: Duration = Duration::from_secs(30);
+
+/// How many of the newest log lines per device the live page shows.
+const LIVE_LOG_LINES: usize = 20;
+
+/// Streams the events of simulation `id` into the app.
+fn live_events(client: Arc<api::Client>, id: Uuid) -> Subscription<Message> {
+    Subscription::run_with_id(
+        id,
+        cosmic::iced::stream::channel(64, move |mut output| async move {
+            use cosmic::iced::futures::SinkExt;
+
+            let mut events = match client.simulation_events(id).await {
+                Ok(events) => events,
+                Err(e) => {
+                    let e = format!("Failed following the simulation: {e}");
+                    let _ = output.send(Message::LiveEvent(id, Err(e))).await;
+                    return;
+                }
+            };
+            while let Some(event) = events.next().await {
+                let event = event.map_err(|e| format!("{e:#}"));
+                if output.send(Message::LiveEvent(id, event)).await.is_err() {
+                    return;
+                }
+            }
+            let _ = output.send(Message::LiveEnded(id)).await;
+        }),
+    )
+}
+
+/// Fetches the log of each of `devices`, reporting them one by one.
+fn fetch_device_logs(
+    client: Arc<api::Client>,
+    id: Uuid,
+    devices: Vec<DeviceName>,
+    generation: u64,
+) -> impl cosmic::iced::futures::Stream<Item = Message> {
+    cosmic::iced::stream::channel(api::LOG_CONCURRENCY, move |mut output| async move {
+        use cosmic::iced::futures::{SinkExt, StreamExt};
+
+        let mut logs = std::pin::pin!(client.fetch_all_device_logs(id, &devices));
+        while let Some((device, log)) = logs.next().await {
+            let log = log.map(ParsedText::parse).map_err(|e| format!("{e}"));
+            if output
+                .send(Message::DeviceLogFetched(generation, device, log))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    })
+}
+
+/// Reports clicks on the open action of desktop notifications, and their closing.
+fn notification_actions() -> Subscription<Message> {
+    struct NotificationActions;
+
+    Subscription::run_with_id(
+        std::any::TypeId::of::<NotificationActions>(),
+        cosmic::iced::stream::channel(16, |mut output| async move {
+            use cosmic::iced::futures::{SinkExt, StreamExt};
+
+            let events = match notification::events().await {
+                Ok(events) => events,
+                Err(e) => {
+                    let e = format!("Failed listening to notifications: {e}");
+                    let _ = output.send(Message::Notified(None, Err(e))).await;
+                    return;
+                }
+            };
+            let mut events = std::pin::pin!(events);
+            while let Some(event) = events.next().await {
+                let message = match event {
+                    notification::Event::Opened(id) => Message::OpenNotification(id),
+                    notification::Event::Closed(id) => Message::NotificationClosed(id),
+                };
+                if output.send(message).await.is_err() {
+                    return;
+                }
+            }
+        }),
+    )
+}
+
+/// Choices offered for the request settings, in seconds or retries.
+const CONNECT_TIMEOUTS: [u32; 4] = [5, 10, 30, 60];
+const CONNECT_TIMEOUT_LABELS: [&str; 4] = ["5 seconds", "10 seconds", "30 seconds", "1 minute"];
+const REQUEST_TIMEOUTS: [u32; 5] = [10, 30, 60, 120, 300];
+const REQUEST_TIMEOUT_LABELS: [&str; 5] = [
+    "10 seconds",
+    "30 seconds",
+    "1 minute",
+    "2 minutes",
+    "5 minutes",
+];
+const RETRIES: [u32; 4] = [0, 1, 3, 5];
+const RETRY_LABELS: [&str; 4] = ["Never", "Once", "3 times", "5 times"];
+
+/// The request settings with the command line overrides applied.
+fn request_policy(config: &Config, args: &cli::Args) -> api::RequestPolicy {
+    let seconds = |secs: u32| Duration::from_secs(secs.into());
+    api::RequestPolicy {
+        connect_timeout: seconds(args.connect_timeout.unwrap_or(config.connect_timeout)),
+        timeout: seconds(args.timeout.unwrap_or(config.request_timeout)),
+        retries: args.retries.unwrap_or(config.retries),
+        idempotent_submit: config.idempotent_submit,
+    }
+}
+
+/// How many simulations are fetched at once.
+const PAGE_SIZE: usize = 100;
+
+/// A row of the simulation list.
+enum ListRow<'a> {
+    Day(jiff::civil::Date),
+    Simulation(&'a api::SimulationOverview),
+}
+
+/// Scroll offset and height of the simulation list, guessed until it was first scrolled.
+fn list_window(viewport: Option<scrollable::Viewport>) -> (f32, f32) {
+    match viewport {
+        Some(viewport) => (viewport.absolute_offset().y, viewport.bounds().height),
+        None => (0.0, 1080.0),
+    }
+}
+
+/// A status shown in the header bar or in place of a panel's content.
+fn status_line<'a>(icon: &'static str, label: String) -> Element<'a, Message> {
+    widget::row()
+        .push(widget::icon::from_name(icon))
+        .push(widget::text(label))
+        .align_y(Alignment::Center)
+        .spacing(theme::active().cosmic().spacing.space_xxs)
+        .into()
+}
+
+/// How far fetching each device's log got, with the devices that failed.
+fn device_logs_progress<'a>(logs: &DeviceLogs) -> Element<'a, Message> {
+    let mut status = format!("Fetched {} of {} device logs", logs.logs.len(), logs.total);
+    if !logs.errors.is_empty() {
+        status.push_str(&format!(", {} failed", logs.errors.len()));
+    }
+    let fetching = logs.done() < logs.total;
+    widget::column()
+        .push_maybe(fetching.then(|| {
+            cosmic::iced::widget::progress_bar(0.0..=logs.total as f32, logs.done() as f32)
+        }))
+        .push(widget::text::caption(status))
+        .extend(
+            logs.errors.iter().map(|(device, e)| {
+                widget::text::caption(format!("{}: {e}", device.as_str())).into()
+            }),
+        )
+        .spacing(theme::active().cosmic().spacing.space_xxs)
+        .into()
+}
+
+/// A line of a comparison, coloured by how it changed.
+fn diff_text<'a>(line: &str, kind: RowKind) -> Element<'a, Message> {
+    let theme = theme::active();
+    let color = match kind {
+        RowKind::Same => return widget::text::monotext(line.to_string()).into(),
+        RowKind::Added => theme.cosmic().success_color(),
+        RowKind::Removed => theme.cosmic().destructive_color(),
+        RowKind::Changed => theme.cosmic().warning_color(),
+    };
+    widget::text::monotext(line.to_string())
+        .class(theme::Text::Color(color.into()))
+        .into()
+}
+
+/// Shown while a panel waits for its request.
+fn loading<'a>(label: &str) -> Element<'a, Message> {
+    status_line("process-working-symbolic", label.to_string())
+}
+
+/// How many files are remembered in "Open recent".
+const MAX_RECENT_FILES: usize = 10;
+
+/// Asks the user for a file to open, `None` if the dialog was cancelled.
+async fn choose_open_path(
+    title: &'static str,
+    filter: FileFilter,
+) -> Result<Option<PathBuf>, String> {
+    let Ok(file) = file_chooser::open::Dialog::new()
+        .title(title)
+        .filter(filter)
+        .open_file()
+        .await
+    else {
+        return Ok(None);
+    };
+    file.url()
+        .to_file_path()
+        .map(Some)
+        .map_err(|()| format!("Not a local file: {}", file.url()))
+}
+
+/// Asks the user where to save a file, `None` if the dialog was cancelled.
+async fn choose_save_path(title: &'static str, file_name: &str) -> Option<PathBuf> {
+    file_chooser::save::Dialog::new()
+        .title(title)
+        .file_name(file_name)
+        .save_file()
+        .await
+        .ok()?
+        .url()?
+        .to_file_path()
+        .ok()
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("Failed reading {}: {e}", path.display()))
+}
+
+fn write_file(path: &Path, content: impl AsRef<[u8]>) -> Result<(), String> {
+    std::fs::write(path, content).map_err(|e| format!("Failed writing {}: {e}", path.display()))
+}
+
+/// The context page to display in the context drawer.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ContextPage {
+    #[default]
+    About,
+    Settings,
+}
+
+/// Files of a profile's [`tls::TlsOptions`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TlsFile {
+    CaBundle,
+    ClientCertificate,
+    ClientKey,
+}
+
+impl TlsFile {
+    pub fn label(self) -> &'static str {
+        match self {
+            TlsFile::CaBundle => "Certificate authorities",
+            TlsFile::ClientCertificate => "Client certificate",
+            TlsFile::ClientKey => "Client key",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            TlsFile::CaBundle => "Choose certificate authorities",
+            TlsFile::ClientCertificate => "Choose client certificate",
+            TlsFile::ClientKey => "Choose client key",
+        }
+    }
+
+    fn filter(self) -> FileFilter {
+        match self {
+            TlsFile::CaBundle | TlsFile::ClientKey => FileFilter::new("PEM")
+                .glob("*.pem")
+                .glob("*.crt")
+                .glob("*.key"),
+            TlsFile::ClientCertificate => FileFilter::new("Certificate")
+                .glob("*.pem")
+                .glob("*.crt")
+                .glob("*.p12")
+                .glob("*.pfx"),
+        }
+    }
+
+    fn path_mut(self, options: &mut tls::TlsOptions) -> &mut Option<PathBuf> {
+        match self {
+            TlsFile::CaBundle => &mut options.ca_bundle,
+            TlsFile::ClientCertificate => &mut options.client_certificate,
+            TlsFile::ClientKey => &mut options.client_key,
+        }
+    }
+}
+
+/// Formats the output panel can be copied in.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CopyFormat {
+    Raw,
+    PrettyJson,
+    Csv,
+    Markdown,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 4] = [
+        CopyFormat::Raw,
+        CopyFormat::PrettyJson,
+        CopyFormat::Csv,
+        CopyFormat::Markdown,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyFormat::Raw => "Text",
+            CopyFormat::PrettyJson => "JSON",
+            CopyFormat::Csv => "CSV",
+            CopyFormat::Markdown => "Markdown",
+        }
+    }
+}
+
+/// The latest request filling a part of the window, which replaces earlier ones.
+#[derive(Default)]
+struct LatestRequest {
+    /// Sent along with the response, which is dropped if another request came after.
+    generation: u64,
+    /// Aborts the request in flight when dropped.
+    handle: Option<task::Handle>,
+}
+
+impl LatestRequest {
+    /// Aborts the request in flight, its response won't be used anymore.
+    fn cancel(&mut self) {
+        self.generation += 1;
+        self.handle = None;
+    }
+
+    /// Starts the request `task` builds for its generation, replacing the one in flight.
+    fn start(&mut self, task: impl FnOnce(u64) -> Task<Message>) -> Task<Message> {
+        self.cancel();
+        let (task, handle) = task(self.generation).abortable();
+        self.handle = Some(handle.abort_on_drop());
+        task
+    }
+
+    /// Whether a response is to the latest request, which is done then.
+    fn finish(&mut self, generation: u64) -> bool {
+        let latest = generation == self.generation;
+        if latest {
+            self.handle = None;
+        }
+        latest
+    }
+
+    fn loading(&self) -> bool {
+        self.handle.is_some()
+    }
+}
+
+/// Logs of every device of a simulation, filled in as they arrive.
+struct DeviceLogs {
+    id: Uuid,
+    /// How many devices the logs were requested for.
+    total: usize,
+    logs: Vec<(DeviceName, ParsedText)>,
+    /// Devices whose log couldn't be fetched, with why.
+    errors: Vec<(DeviceName, String)>,
+}
+
+impl DeviceLogs {
+    /// How many devices are done, successfully or not.
+    fn done(&self) -> usize {
+        self.logs.len() + self.errors.len()
+    }
+
+    fn log(&self, id: Uuid, device: &DeviceName) -> Option<&ParsedText> {
+        if id != self.id {
+            return None;
+        }
+        self.logs
+            .iter()
+            .find(|(name, _)| name.as_str() == device.as_str())
+            .map(|(_, log)| log)
+    }
+}
+
+/// How many matches the command palette shows at once.
+const PALETTE_ENTRIES: usize = 12;
+
+/// State of the open command palette.
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    query: String,
+    selected: usize,
+}
+
+/// Something that can be picked in the command palette.
+#[derive(Clone, Debug)]
+pub enum PaletteEntry {
+    Action(MenuAction),
+    Simulation(Uuid),
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MenuAction {
+    About,
+    OpenFile,
+    OpenRecent(usize),
+    Save,
+    SaveAs,
+    ImportCsv,
+    ExportResults,
+    ExportAll,
+    GenerateReport,
+    Quit,
+    Undo,
+    Redo,
+    FormatJson,
+    Submit,
+    RefreshList,
+    ClearCache,
+    NewFromTemplate,
+    CopyOutput,
+    CopyAs(CopyFormat),
+    NextSimulation,
+    PreviousSimulation,
+    ToggleSettings,
+    CommandPalette,
+}
+
+impl MenuAction {
+    /// Every action which doesn't carry data, in the order they are offered to the user.
+    pub const ALL: [MenuAction; 21] = [
+        MenuAction::OpenFile,
+        MenuAction::Save,
+        MenuAction::SaveAs,
+        MenuAction::ImportCsv,
+        MenuAction::ExportResults,
+        MenuAction::ExportAll,
+        MenuAction::GenerateReport,
+        MenuAction::Undo,
+        MenuAction::Redo,
+        MenuAction::FormatJson,
+        MenuAction::Submit,
+        MenuAction::RefreshList,
+        MenuAction::ClearCache,
+        MenuAction::NewFromTemplate,
+        MenuAction::CopyOutput,
+        MenuAction::NextSimulation,
+        MenuAction::PreviousSimulation,
+        MenuAction::ToggleSettings,
+        MenuAction::CommandPalette,
+        MenuAction::About,
+        MenuAction::Quit,
+    ];
+
+    /// Stable identifier used to store key bindings in the config.
+    pub fn id(self) -> &'static str {
+        match self {
+            MenuAction::About => "about",
+            MenuAction::OpenFile => "open-file",
+            MenuAction::OpenRecent(_) => "open-recent",
+            MenuAction::Save => "save",
+            MenuAction::SaveAs => "save-as",
+            MenuAction::ImportCsv => "import-csv",
+            MenuAction::ExportResults => "export-results",
+            MenuAction::ExportAll => "export-all",
+            MenuAction::GenerateReport => "generate-report",
+            MenuAction::Quit => "quit",
+            MenuAction::Undo => "undo",
+            MenuAction::Redo => "redo",
+            MenuAction::FormatJson => "format-json",
+            MenuAction::Submit => "submit",
+            MenuAction::RefreshList => "refresh-list",
+            MenuAction::ClearCache => "clear-cache",
+            MenuAction::NewFromTemplate => "new-from-template",
+            MenuAction::CopyOutput => "copy-output",
+            MenuAction::CopyAs(_) => "copy-output-as",
+            MenuAction::NextSimulation => "next-simulation",
+            MenuAction::PreviousSimulation => "previous-simulation",
+            MenuAction::ToggleSettings => "toggle-settings",
+            MenuAction::CommandPalette => "command-palette",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MenuAction::About => "About",
+            MenuAction::OpenFile => "Open simulation file",
+            MenuAction::OpenRecent(_) => "Open recent simulation file",
+            MenuAction::Save => "Save simulation file",
+            MenuAction::SaveAs => "Save simulation file as",
+            MenuAction::ImportCsv => "Import devices from CSV",
+            MenuAction::ExportResults => "Export simulation results",
+            MenuAction::ExportAll => "Export all simulations",
+            MenuAction::GenerateReport => "Generate report",
+            MenuAction::Quit => "Quit",
+            MenuAction::Undo => "Undo",
+            MenuAction::Redo => "Redo",
+            MenuAction::FormatJson => "Format JSON",
+            MenuAction::Submit => "Submit simulation",
+            MenuAction::RefreshList => "Refresh simulation list",
+            MenuAction::ClearCache => "Clear cache",
+            MenuAction::NewFromTemplate => "New simulation from template",
+            MenuAction::CopyOutput => "Copy output",
+            MenuAction::CopyAs(_) => "Copy output as",
+            MenuAction::NextSimulation => "Next simulation",
+            MenuAction::PreviousSimulation => "Previous simulation",
+            MenuAction::ToggleSettings => "Toggle settings",
+            MenuAction::CommandPalette => "Command palette",
+        }
+    }
+}
+
+/// Actions on one simulation, offered in the context menu of list rows.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RowAction {
+    Rerun(Uuid),
+    CloneToEditor(Uuid),
+    Delete(Uuid),
+}
+
+impl RowAction {
+    pub fn id(self) -> Uuid {
+        match self {
+            RowAction::Rerun(id) | RowAction::CloneToEditor(id) | RowAction::Delete(id) => id,
+        }
+    }
+}
+
+impl menu::action::MenuAction for RowAction {
+    type Message = Message;
+
+    fn message(&self) -> Self::Message {
+        Message::ConfirmRowAction(*self)
+    }
 }
 
 impl menu::action::MenuAction for MenuAction {
@@ -438,7 +4315,28 @@ impl menu::action::MenuAction for MenuAction {
     fn message(&self) -> Self::Message {
         match self {
             MenuAction::About => Message::ToggleContextPage(ContextPage::About),
-            MenuAction::OpenFile => Message::NewSimulation,
+            MenuAction::OpenFile => Message::OpenFile,
+            MenuAction::OpenRecent(i) => Message::OpenRecent(*i),
+            MenuAction::Save => Message::Save,
+            MenuAction::SaveAs => Message::SaveAs,
+            MenuAction::ImportCsv => Message::ImportCsv,
+            MenuAction::ExportResults => Message::ExportResults,
+            MenuAction::ExportAll => Message::ExportAll,
+            MenuAction::GenerateReport => Message::GenerateReport,
+            MenuAction::Quit => Message::Quit,
+            MenuAction::Undo => Message::Undo,
+            MenuAction::Redo => Message::Redo,
+            MenuAction::FormatJson => Message::FormatJson,
+            MenuAction::Submit => Message::Submit,
+            MenuAction::RefreshList => Message::FetchAllSimulations,
+            MenuAction::ClearCache => Message::ClearCache,
+            MenuAction::NewFromTemplate => Message::NewSimulation,
+            MenuAction::CopyOutput => Message::CopyTextToClipboard,
+            MenuAction::CopyAs(format) => Message::CopyAs(*format),
+            MenuAction::NextSimulation => Message::NextSimulation,
+            MenuAction::PreviousSimulation => Message::PreviousSimulation,
+            MenuAction::ToggleSettings => Message::ToggleContextPage(ContextPage::Settings),
+            MenuAction::CommandPalette => Message::TogglePalette,
         }
     }
 }
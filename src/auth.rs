@@ -0,0 +1,595 @@
+//! Authenticating with the server, with credentials kept in the secret service.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use base64::Engine;
+use eyre::{bail, WrapErr};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use uuid::Uuid;
+
+/// How to authenticate, as stored in the settings. Secrets are kept in the keyring.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuthMethod {
+    #[default]
+    None,
+    Bearer,
+    Basic {
+        username: String,
+    },
+    OAuth(OAuthSettings),
+}
+
+impl AuthMethod {
+    pub const LABELS: [&'static str; 4] =
+        ["None", "Bearer token", "Username and password", "OAuth 2"];
+
+    /// Position in [`Self::LABELS`].
+    pub fn index(&self) -> usize {
+        match self {
+            AuthMethod::None => 0,
+            AuthMethod::Bearer => 1,
+            AuthMethod::Basic { .. } => 2,
+            AuthMethod::OAuth(_) => 3,
+        }
+    }
+
+    /// The method at `index` in [`Self::LABELS`].
+    pub fn from_index(index: usize) -> Option<Self> {
+        Some(match index {
+            0 => AuthMethod::None,
+            1 => AuthMethod::Bearer,
+            2 => AuthMethod::Basic {
+                username: String::new(),
+            },
+            3 => AuthMethod::OAuth(OAuthSettings::default()),
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OAuthSettings {
+    /// The authorization server, which must support discovery.
+    pub issuer: String,
+    pub client_id: String,
+    /// Space separated scopes to request, may be empty.
+    pub scope: String,
+    pub flow: OAuthFlow,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OAuthFlow {
+    /// The user enters a code on any device, nothing has to reach this computer.
+    #[default]
+    DeviceCode,
+    /// The browser redirects back to a port opened here, secured with PKCE.
+    Pkce,
+}
+
+impl OAuthFlow {
+    pub const ALL: [Self; 2] = [OAuthFlow::DeviceCode, OAuthFlow::Pkce];
+    pub const LABELS: [&'static str; 2] = ["Device code", "Browser"];
+}
+
+/// Secrets proving who we are to the server.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Credentials {
+    Bearer { token: String },
+    Basic { username: String, password: String },
+    OAuth(Tokens),
+}
+
+/// Tokens from an OAuth login, with what is needed to refresh them.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tokens {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub token_endpoint: String,
+    pub client_id: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // never print secrets
+        match self {
+            Credentials::Bearer { .. } => f.write_str("Bearer"),
+            Credentials::Basic { username, .. } => write!(f, "Basic({username})"),
+            Credentials::OAuth(tokens) => write!(f, "OAuth({})", tokens.token_endpoint),
+        }
+    }
+}
+
+/// The credentials used for requests to one server.
+pub struct Session {
+    /// The server, which also identifies the credentials in the keyring.
+    server: String,
+    credentials: RwLock<Option<Credentials>>,
+    /// Held while refreshing, so parallel requests rejected together refresh only once.
+    refreshing: tokio::sync::Mutex<()>,
+    /// Why refreshed credentials couldn't be stored, until the app reports it.
+    store_error: Mutex<Option<String>>,
+}
+
+impl Session {
+    pub fn new(server: String) -> Self {
+        Self {
+            server,
+            credentials: RwLock::new(None),
+            refreshing: tokio::sync::Mutex::new(()),
+            store_error: Mutex::new(None),
+        }
+    }
+
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    pub fn set(&self, credentials: Option<Credentials>) {
+        *self.credentials.write().unwrap() = credentials;
+    }
+
+    /// The OAuth access token requests are sent with, if any.
+    pub fn access_token(&self) -> Option<String> {
+        match &*self.credentials.read().unwrap() {
+            Some(Credentials::OAuth(tokens)) => Some(tokens.access_token.clone()),
+            _ => None,
+        }
+    }
+
+    /// Takes the failure to store refreshed credentials in the keyring, if there was one.
+    pub fn take_store_error(&self) -> Option<String> {
+        self.store_error.lock().unwrap().take()
+    }
+
+    /// Adds the credentials, if any, to `request`.
+    pub fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &*self.credentials.read().unwrap() {
+            None => request,
+            Some(Credentials::Bearer { token }) => request.bearer_auth(token),
+            Some(Credentials::Basic { username, password }) => {
+                request.basic_auth(username, Some(password))
+            }
+            Some(Credentials::OAuth(tokens)) => request.bearer_auth(&tokens.access_token),
+        }
+    }
+
+    /// Gets a new access token after the server rejected `rejected`, the token a request
+    /// was sent with.
+    ///
+    /// Returns whether there is a new token worth retrying with, which may come from
+    /// another request refreshing first.
+    pub async fn refresh(
+        &self,
+        http: &reqwest::Client,
+        rejected: Option<&str>,
+    ) -> eyre::Result<bool> {
+        let _refreshing = self.refreshing.lock().await;
+        let tokens = match &*self.credentials.read().unwrap() {
+            Some(Credentials::OAuth(tokens)) if Some(tokens.access_token.as_str()) != rejected => {
+                return Ok(true);
+            }
+            Some(Credentials::OAuth(tokens)) if tokens.refresh_token.is_some() => tokens.clone(),
+            _ => return Ok(false),
+        };
+        let refresh_token = tokens.refresh_token.as_deref().unwrap_or_default();
+        let res = request_token(
+            http,
+            &tokens.token_endpoint,
+            &[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("client_id", &tokens.client_id),
+            ],
+        )
+        .await?;
+        let refreshed = Credentials::OAuth(Tokens {
+            access_token: res.access_token,
+            // servers may keep the refresh token as it is
+            refresh_token: res.refresh_token.or(tokens.refresh_token),
+            ..tokens
+        });
+        self.set(Some(refreshed.clone()));
+        // the new token works without being stored, it just won't outlive the app
+        if let Err(e) = store(&self.server, &refreshed).await {
+            *self.store_error.lock().unwrap() =
+                Some(format!("Failed storing refreshed credentials: {e:#}"));
+        }
+        Ok(true)
+    }
+}
+
+/// Value of the `application` attribute of our keyring items.
+const KEYRING_APPLICATION: &str = "ha-ui";
+
+fn attributes(server: &str) -> HashMap<&str, &str> {
+    HashMap::from([("application", KEYRING_APPLICATION), ("server", server)])
+}
+
+/// Loads the credentials for `server` from the keyring.
+pub async fn load(server: &str) -> eyre::Result<Option<Credentials>> {
+    let keyring = oo7::Keyring::new().await?;
+    let Some(item) = keyring
+        .search_items(&attributes(server))
+        .await?
+        .into_iter()
+        .next()
+    else {
+        return Ok(None);
+    };
+    let secret = item.secret().await?;
+    let credentials = serde_json::from_slice(&secret).wrap_err("Invalid stored credentials")?;
+    Ok(Some(credentials))
+}
+
+/// Stores the credentials for `server` in the keyring, replacing earlier ones.
+pub async fn store(server: &str, credentials: &Credentials) -> eyre::Result<()> {
+    let keyring = oo7::Keyring::new().await?;
+    let secret = serde_json::to_vec(credentials)?;
+    keyring
+        .create_item(
+            &format!("ha-ui credentials for {server}"),
+            &attributes(server),
+            secret,
+            true,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Removes the credentials for `server` from the keyring.
+pub async fn delete(server: &str) -> eyre::Result<()> {
+    let keyring = oo7::Keyring::new().await?;
+    keyring.delete(&attributes(server)).await?;
+    Ok(())
+}
+
+/// Attributes of the item with the password of the client certificate for `server`,
+/// kept apart from the credentials so signing out doesn't remove it.
+fn certificate_attributes(server: &str) -> HashMap<&str, &str> {
+    HashMap::from([
+        ("application", KEYRING_APPLICATION),
+        ("certificate", server),
+    ])
+}
+
+/// Loads the password of the PKCS#12 client certificate for `server` from the keyring.
+pub async fn load_certificate_password(server: &str) -> eyre::Result<Option<String>> {
+    let keyring = oo7::Keyring::new().await?;
+    let Some(item) = keyring
+        .search_items(&certificate_attributes(server))
+        .await?
+        .into_iter()
+        .next()
+    else {
+        return Ok(None);
+    };
+    let secret = item.secret().await?;
+    let password = String::from_utf8(secret.to_vec()).wrap_err("Invalid stored password")?;
+    Ok(Some(password))
+}
+
+/// Stores the password of the client certificate for `server` in the keyring.
+pub async fn store_certificate_password(server: &str, password: &str) -> eyre::Result<()> {
+    let keyring = oo7::Keyring::new().await?;
+    keyring
+        .create_item(
+            &format!("ha-ui client certificate password for {server}"),
+            &certificate_attributes(server),
+            password.as_bytes().to_vec(),
+            true,
+        )
+        .await?;
+    Ok(())
+}
+
+/// The parts of the authorization server metadata we use.
+#[derive(Deserialize)]
+struct Metadata {
+    authorization_endpoint: Option<String>,
+    token_endpoint: String,
+    device_authorization_endpoint: Option<String>,
+}
+
+async fn discover(http: &reqwest::Client, issuer: &str) -> eyre::Result<Metadata> {
+    let issuer = issuer.trim_end_matches('/');
+    let mut last_error = None;
+    for well_known in ["openid-configuration", "oauth-authorization-server"] {
+        let url = format!("{issuer}/.well-known/{well_known}");
+        match http
+            .get(&url)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+        {
+            Ok(res) => {
+                return res
+                    .json()
+                    .await
+                    .wrap_err("Invalid authorization server metadata")
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap()).wrap_err_with(|| format!("Failed discovering {issuer}"))
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error_description {
+            Some(description) => write!(f, "{description} ({})", self.error),
+            None => f.write_str(&self.error),
+        }
+    }
+}
+
+/// Posts `form` to the token endpoint, returning the server's error code on failure.
+async fn try_request_token(
+    http: &reqwest::Client,
+    endpoint: &str,
+    form: &[(&str, &str)],
+) -> eyre::Result<Result<TokenResponse, TokenError>> {
+    let res = http.post(endpoint).form(form).send().await?;
+    let success = res.status().is_success();
+    let body = res.text().await?;
+    Ok(if success {
+        Ok(serde_json::from_str(&body).wrap_err("Invalid token response")?)
+    } else {
+        Err(serde_json::from_str(&body).unwrap_or_else(|_| TokenError {
+            error: "invalid_response".to_string(),
+            error_description: Some(body),
+        }))
+    })
+}
+
+async fn request_token(
+    http: &reqwest::Client,
+    endpoint: &str,
+    form: &[(&str, &str)],
+) -> eyre::Result<TokenResponse> {
+    match try_request_token(http, endpoint, form).await? {
+        Ok(res) => Ok(res),
+        Err(e) => bail!("Token request failed: {e}"),
+    }
+}
+
+/// A device login waiting for the user to enter the code.
+#[derive(Debug, Clone)]
+pub struct DeviceLogin {
+    pub user_code: String,
+    pub verification_uri: String,
+    /// The verification page with the code filled in, if the server offers one.
+    pub verification_uri_complete: Option<String>,
+    device_code: String,
+    interval: Duration,
+    expires_in: Duration,
+    token_endpoint: String,
+    client_id: String,
+}
+
+#[derive(Deserialize)]
+struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    #[serde(alias = "verification_url")]
+    verification_uri: String,
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    interval: Option<u64>,
+}
+
+/// Starts a device code login (RFC 8628), the user then enters the code in a browser.
+pub async fn start_device_login(
+    http: &reqwest::Client,
+    settings: &OAuthSettings,
+) -> eyre::Result<DeviceLogin> {
+    let metadata = discover(http, &settings.issuer).await?;
+    let Some(endpoint) = metadata.device_authorization_endpoint else {
+        bail!("{} doesn't support device code logins", settings.issuer);
+    };
+    let auth: DeviceAuthorization = http
+        .post(endpoint)
+        .form(&[
+            ("client_id", settings.client_id.as_str()),
+            ("scope", settings.scope.as_str()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .wrap_err("Invalid device authorization response")?;
+    Ok(DeviceLogin {
+        user_code: auth.user_code,
+        verification_uri: auth.verification_uri,
+        verification_uri_complete: auth.verification_uri_complete,
+        device_code: auth.device_code,
+        interval: Duration::from_secs(auth.interval.unwrap_or(5)),
+        expires_in: Duration::from_secs(auth.expires_in),
+        token_endpoint: metadata.token_endpoint,
+        client_id: settings.client_id.clone(),
+    })
+}
+
+/// Waits until the user entered the code of `login`.
+pub async fn finish_device_login(
+    http: &reqwest::Client,
+    login: DeviceLogin,
+) -> eyre::Result<Credentials> {
+    let deadline = tokio::time::Instant::now() + login.expires_in;
+    let mut interval = login.interval;
+    let form = [
+        ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+        ("device_code", login.device_code.as_str()),
+        ("client_id", login.client_id.as_str()),
+    ];
+    loop {
+        tokio::time::sleep(interval).await;
+        if tokio::time::Instant::now() > deadline {
+            bail!("The login code expired");
+        }
+        match try_request_token(http, &login.token_endpoint, &form).await? {
+            Ok(res) => {
+                return Ok(Credentials::OAuth(Tokens {
+                    access_token: res.access_token,
+                    refresh_token: res.refresh_token,
+                    token_endpoint: login.token_endpoint,
+                    client_id: login.client_id,
+                }))
+            }
+            Err(e) if e.error == "authorization_pending" => {}
+            Err(e) if e.error == "slow_down" => interval += Duration::from_secs(5),
+            Err(e) => bail!("Login failed: {e}"),
+        }
+    }
+}
+
+/// How long the browser login may take.
+const BROWSER_LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The PKCE code challenge for `verifier` (RFC 7636, S256).
+fn code_challenge(verifier: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(verifier))
+}
+
+/// Logs in through the browser, which redirects back to a port opened here.
+pub async fn browser_login(
+    http: &reqwest::Client,
+    settings: &OAuthSettings,
+) -> eyre::Result<Credentials> {
+    let metadata = discover(http, &settings.issuer).await?;
+    let Some(authorize) = metadata.authorization_endpoint else {
+        bail!("{} doesn't support browser logins", settings.issuer);
+    };
+    let mut authorize = Url::parse(&authorize).wrap_err("Invalid authorization endpoint")?;
+
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await?;
+    let redirect_uri = format!("http://127.0.0.1:{}/", listener.local_addr()?.port());
+    let verifier = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let state = Uuid::new_v4().simple().to_string();
+    authorize
+        .query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &settings.client_id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("scope", &settings.scope)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &code_challenge(&verifier))
+        .append_pair("code_challenge_method", "S256");
+    open::that_detached(authorize.as_str()).wrap_err("Failed opening the browser")?;
+
+    let code = tokio::time::timeout(BROWSER_LOGIN_TIMEOUT, receive_code(&listener, &state))
+        .await
+        .wrap_err("The login timed out")??;
+    let res = request_token(
+        http,
+        &metadata.token_endpoint,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("client_id", &settings.client_id),
+            ("code_verifier", &verifier),
+        ],
+    )
+    .await?;
+    Ok(Credentials::OAuth(Tokens {
+        access_token: res.access_token,
+        refresh_token: res.refresh_token,
+        token_endpoint: metadata.token_endpoint,
+        client_id: settings.client_id.clone(),
+    }))
+}
+
+/// Accepts the browser's redirect, returning the authorization code.
+async fn receive_code(listener: &tokio::net::TcpListener, state: &str) -> eyre::Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut buf = vec![0; 8192];
+        let len = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..len]);
+        // browsers may also ask for a favicon, only the redirect has a code or error
+        let Some(params) = redirect_params(&request) else {
+            let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\n\r\n").await;
+            continue;
+        };
+        let page = "<html><body>You can close this window and return to ha-ui.</body></html>";
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{page}",
+            page.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+
+        if params.get("state").map(String::as_str) != Some(state) {
+            bail!("The login response doesn't belong to this login");
+        }
+        if let Some(error) = params.get("error") {
+            bail!("Login failed: {error}");
+        }
+        return Ok(params["code"].clone());
+    }
+}
+
+/// Query parameters of a redirect request, `None` for other requests.
+fn redirect_params(request: &str) -> Option<HashMap<String, String>> {
+    let target = request
+        .lines()
+        .next()?
+        .strip_prefix("GET ")?
+        .split(' ')
+        .next()?;
+    let url = Url::parse("http://localhost").ok()?.join(target).ok()?;
+    let params = url.query_pairs().into_owned().collect::<HashMap<_, _>>();
+    (params.contains_key("code") || params.contains_key("error")).then_some(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_challenge_matches_rfc_example() {
+        // RFC 7636, appendix B
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-1B5mPNpXmE42TP6JaEvMHk3-Tm7IyuO7R2bEJdzAXeq7MhS"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn reads_redirect_parameters() {
+        let params =
+            redirect_params("GET /?code=a%2Fb&state=xyz HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n")
+                .unwrap();
+        assert_eq!(params["code"], "a/b");
+        assert_eq!(params["state"], "xyz");
+        assert!(redirect_params("GET /favicon.ico HTTP/1.1\r\n\r\n").is_none());
+    }
+
+    #[test]
+    fn debug_hides_secrets() {
+        let credentials = Credentials::Basic {
+            username: "ada".to_string(),
+            password: "hunter2".to_string(),
+        };
+        assert_eq!(format!("{credentials:?}"), "Basic(ada)");
+    }
+}
@@ -0,0 +1,233 @@
+//! Responses kept to avoid downloading what can't change again.
+//!
+//! Reports, sources and logs of finished simulations are immutable, so they are kept in
+//! memory and, if enabled, in the XDG cache directory. The simulation list changes, it is
+//! remembered with its ETag to revalidate it and to browse it while offline.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+
+use eyre::WrapErr;
+use sha2::{Digest, Sha256};
+
+/// How many bytes of responses are kept in memory.
+pub const MEMORY_BUDGET: usize = 32 * 1024 * 1024;
+
+/// `$XDG_CACHE_HOME/ha-ui`, falling back to `~/.cache/ha-ui`.
+pub fn default_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("ha-ui"))
+}
+
+/// A response body with its ETag.
+pub type Tagged = (Option<String>, Arc<str>);
+
+pub struct Cache {
+    memory: Mutex<Lru>,
+    /// Directory responses are also written to, `None` to keep them in memory only.
+    dir: RwLock<Option<PathBuf>>,
+    /// Responses which may change, with the ETag they were sent with if any.
+    tagged: Mutex<HashMap<String, Tagged>>,
+}
+
+impl Cache {
+    pub fn new(budget: usize, dir: Option<PathBuf>) -> Self {
+        Self {
+            memory: Mutex::new(Lru::new(budget)),
+            dir: RwLock::new(dir),
+            tagged: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Starts or stops keeping responses on disk, those already there stay until cleared.
+    pub fn set_dir(&self, dir: Option<PathBuf>) {
+        *self.dir.write().unwrap() = dir;
+    }
+
+    pub fn get(&self, key: &str) -> Option<Arc<str>> {
+        if let Some(value) = self.memory.lock().unwrap().get(key) {
+            return Some(value);
+        }
+        let value: Arc<str> = self.read(key)?.into();
+        self.memory.lock().unwrap().insert(key, value.clone());
+        Some(value)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn insert(&self, key: &str, value: Arc<str>) {
+        self.write(key, &value);
+        self.memory.lock().unwrap().insert(key, value);
+    }
+
+    /// The body last received for `key` and its ETag.
+    pub fn tagged(&self, key: &str) -> Option<Tagged> {
+        if let Some(tagged) = self.tagged.lock().unwrap().get(key) {
+            return Some(tagged.clone());
+        }
+        // stored as the ETag on the first line, which is empty without one
+        let stored = self.read(key)?;
+        let (etag, body) = stored.split_once('\n')?;
+        let tagged = (
+            Some(etag.to_string()).filter(|e| !e.is_empty()),
+            body.into(),
+        );
+        self.tagged
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), tagged.clone());
+        Some(tagged)
+    }
+
+    pub fn insert_tagged(&self, key: &str, etag: Option<String>, value: Arc<str>) {
+        self.write(
+            key,
+            &format!("{}\n{value}", etag.as_deref().unwrap_or_default()),
+        );
+        self.tagged
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (etag, value));
+    }
+
+    /// Drops everything, from memory and from disk.
+    pub fn clear(&self) -> eyre::Result<()> {
+        self.memory.lock().unwrap().clear();
+        self.tagged.lock().unwrap().clear();
+        let Some(dir) = self.dir.read().unwrap().clone() else {
+            return Ok(());
+        };
+        match std::fs::remove_dir_all(&dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).wrap_err_with(|| format!("Failed removing {}", dir.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn read(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.path(key)?).ok()
+    }
+
+    fn write(&self, key: &str, value: &str) {
+        let Some(path) = self.path(key) else {
+            return;
+        };
+        // best effort, the value stays cached in memory and is fetched again next time
+        let _ = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, value));
+    }
+
+    /// Where `key` is stored on disk, keys are hashed as they contain whole URLs.
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        let dir = self.dir.read().unwrap();
+        let hash = Sha256::digest(key.as_bytes());
+        Some(dir.as_ref()?.join(format!("{hash:x}")))
+    }
+}
+
+/// Values used least recently are dropped once they exceed a size budget.
+struct Lru {
+    budget: usize,
+    size: usize,
+    /// Least recently used first.
+    entries: VecDeque<(String, Arc<str>)>,
+}
+
+impl Lru {
+    fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            size: 0,
+            entries: VecDeque::new(),
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<Arc<str>> {
+        let i = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(i)?;
+        let value = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(value)
+    }
+
+    fn insert(&mut self, key: &str, value: Arc<str>) {
+        if let Some(i) = self.entries.iter().position(|(k, _)| k == key) {
+            let (_, old) = self.entries.remove(i).unwrap();
+            self.size -= old.len();
+        }
+        self.size += value.len();
+        self.entries.push_back((key.to_string(), value));
+        // the newest value stays even if it alone exceeds the budget
+        while self.size > self.budget && self.entries.len() > 1 {
+            let (_, old) = self.entries.pop_front().unwrap();
+            self.size -= old.len();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.size = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_values_go_first() {
+        let mut lru = Lru::new(10);
+        lru.insert("a", "1234".into());
+        lru.insert("b", "1234".into());
+        assert!(lru.get("a").is_some());
+        lru.insert("c", "1234".into());
+        assert!(lru.get("b").is_none());
+        assert_eq!(lru.get("a").as_deref(), Some("1234"));
+        assert_eq!(lru.size, 8);
+
+        lru.insert("d", "0123456789ab".into());
+        assert_eq!(lru.entries.len(), 1);
+        assert_eq!(lru.size, 12);
+    }
+
+    #[test]
+    fn disk_entries_survive_the_memory() {
+        let dir = std::env::temp_dir().join(format!("ha-ui-cache-test-{}", std::process::id()));
+        let cache = Cache::new(MEMORY_BUDGET, Some(dir.clone()));
+        cache.insert("http://host/simulation/1/source", "{}".into());
+
+        let reopened = Cache::new(MEMORY_BUDGET, Some(dir.clone()));
+        assert_eq!(
+            reopened.get("http://host/simulation/1/source").as_deref(),
+            Some("{}")
+        );
+        cache.insert_tagged("http://host/simulation", None, "[]".into());
+        assert_eq!(
+            reopened.tagged("http://host/simulation"),
+            Some((None, "[]".into()))
+        );
+        cache.insert_tagged(
+            "http://host/simulation",
+            Some("\"v2\"".into()),
+            "[1]".into(),
+        );
+        let reopened = Cache::new(MEMORY_BUDGET, Some(dir.clone()));
+        assert_eq!(
+            reopened.tagged("http://host/simulation"),
+            Some((Some("\"v2\"".into()), "[1]".into()))
+        );
+
+        reopened.clear().unwrap();
+        assert!(!dir.exists());
+        assert!(!reopened.contains("http://host/simulation/1/source"));
+    }
+}
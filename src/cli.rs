@@ -0,0 +1,98 @@
+//! Command line options.
+
+pub const USAGE: &str = "\
+Usage: ha-ui [OPTIONS]
+
+Options:
+  --connect-timeout <SECONDS>  Give up connecting to the server after this long
+  --timeout <SECONDS>          Give up on requests taking longer than this
+  --retries <COUNT>            How often failed requests are retried
+  -h, --help                   Print this help
+
+Options override the settings for this run only.";
+
+/// Options given on the command line, `None` where the settings apply.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Args {
+    pub connect_timeout: Option<u32>,
+    pub timeout: Option<u32>,
+    pub retries: Option<u32>,
+    pub help: bool,
+}
+
+impl Args {
+    /// Whether any setting is overridden.
+    pub fn overrides(&self) -> bool {
+        self.connect_timeout.is_some() || self.timeout.is_some() || self.retries.is_some()
+    }
+}
+
+/// Parses the arguments following the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // both `--option value` and `--option=value`
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        let target = match name.as_str() {
+            "-h" | "--help" => {
+                parsed.help = true;
+                continue;
+            }
+            "--connect-timeout" => &mut parsed.connect_timeout,
+            "--timeout" => &mut parsed.timeout,
+            "--retries" => &mut parsed.retries,
+            _ => return Err(format!("Unknown option {name}")),
+        };
+        let value = inline
+            .or_else(|| args.next())
+            .ok_or_else(|| format!("Missing value for {name}"))?;
+        *target = Some(
+            value
+                .parse()
+                .map_err(|_| format!("Invalid value {value:?} for {name}"))?,
+        );
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn parses_separate_and_inline_values() {
+        assert_eq!(
+            args(&["--timeout", "60", "--retries=0"]),
+            Ok(Args {
+                timeout: Some(60),
+                retries: Some(0),
+                ..Args::default()
+            })
+        );
+        assert!(args(&["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(
+            args(&["--retries", "-1"]),
+            Err("Invalid value \"-1\" for --retries".to_string())
+        );
+        assert_eq!(
+            args(&["--timeout"]),
+            Err("Missing value for --timeout".to_string())
+        );
+        assert_eq!(
+            args(&["--verbose"]),
+            Err("Unknown option --verbose".to_string())
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use uuid::Uuid;
+
+use crate::auth::AuthMethod;
+use crate::metadata::SimulationMeta;
+use crate::tls::TlsOptions;
+
+/// Persistent user settings, stored through cosmic-config.
+#[derive(Debug, Clone, CosmicConfigEntry, Eq, PartialEq)]
+#[version = 1]
+pub struct Config {
+    /// User overrides of the default key bindings, keyed by [`crate::app::MenuAction::id`].
+    ///
+    /// The value is a binding like `Ctrl+Shift+P`, an empty string unbinds the action.
+    pub key_binds: BTreeMap<String, String>,
+    /// Files recently opened or saved in the editor, most recent first.
+    pub recent_files: Vec<PathBuf>,
+    /// HTML template for reports, the built-in one is used if unset.
+    pub report_template: Option<PathBuf>,
+    /// Command converting HTML reports to PDF, see [`crate::report::DEFAULT_PDF_COMMAND`].
+    pub pdf_command: Option<String>,
+    /// Whether metadata is also sent to the server, for servers which store it.
+    pub sync_metadata: bool,
+    /// Seconds between checks for new simulations, 0 turns checking off.
+    pub refresh_interval: u32,
+    /// Seconds to wait for a connection to the server.
+    pub connect_timeout: u32,
+    /// Seconds to wait for a response from the server.
+    pub request_timeout: u32,
+    /// How often failed requests for data are retried.
+    pub retries: u32,
+    /// Whether submissions carry an idempotency key, allowing them to be retried too.
+    pub idempotent_submit: bool,
+    /// Whether finished simulations are also cached on disk, in the XDG cache directory.
+    pub disk_cache: bool,
+    /// Whether desktop notifications are shown when simulations end in the background.
+    pub notifications: bool,
+    /// Servers to choose from, never empty.
+    pub profiles: Vec<ServerProfile>,
+    /// Index of the profile in use.
+    pub active_profile: usize,
+}
+
+impl Config {
+    /// The profile in use.
+    pub fn profile(&self) -> &ServerProfile {
+        &self.profiles[self.profile_index()]
+    }
+
+    pub fn profile_mut(&mut self) -> &mut ServerProfile {
+        let i = self.profile_index();
+        &mut self.profiles[i]
+    }
+
+    /// Index of the profile in use, valid even if the stored one isn't.
+    pub fn profile_index(&self) -> usize {
+        self.active_profile.min(self.profiles.len() - 1)
+    }
+}
+
+/// A server to work against.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ServerProfile {
+    pub name: String,
+    pub endpoint: String,
+    /// How to authenticate, the secrets are kept in the keyring.
+    pub auth: AuthMethod,
+    /// Certificates to trust or present, the password of a PKCS#12 file is in the keyring.
+    #[serde(default)]
+    pub tls: TlsOptions,
+}
+
+impl Default for ServerProfile {
+    fn default() -> Self {
+        Self {
+            name: "Local".to_string(),
+            endpoint: "http://localhost:8000".to_string(),
+            auth: AuthMethod::None,
+            tls: TlsOptions::default(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            key_binds: BTreeMap::new(),
+            recent_files: Vec::new(),
+            report_template: None,
+            pdf_command: None,
+            sync_metadata: false,
+            refresh_interval: 30,
+            connect_timeout: 10,
+            request_timeout: 30,
+            retries: 3,
+            idempotent_submit: false,
+            disk_cache: false,
+            notifications: true,
+            profiles: vec![ServerProfile::default()],
+            active_profile: 0,
+        }
+    }
+}
+
+/// Data kept between runs which isn't a setting, stored in the cosmic-config state.
+#[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
+#[version = 1]
+pub struct State {
+    /// Names, tags and notes of simulations.
+    pub metadata: BTreeMap<Uuid, SimulationMeta>,
+    /// Simulations submitted from the editor, newest first.
+    pub submissions: Vec<Submission>,
+    /// Simulations submitted while offline, oldest first, sent once the server is back.
+    pub queued: Vec<QueuedSubmission>,
+}
+
+/// A simulation submitted from the editor.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Submission {
+    pub id: Uuid,
+    pub timestamp: jiff::Timestamp,
+    pub result: i64,
+    /// The file open in the editor at the time, if it was saved.
+    pub file: Option<PathBuf>,
+}
+
+/// A simulation waiting for its server to be reachable.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct QueuedSubmission {
+    /// URL of the server it is meant for.
+    pub server: String,
+    /// The simulation as JSON, which was valid when it was queued.
+    pub simulation: String,
+    pub timestamp: jiff::Timestamp,
+    /// The file open in the editor at the time, if it was saved.
+    pub file: Option<PathBuf>,
+}
@@ -0,0 +1,105 @@
+//! Minimal RFC 4180 style CSV reading and writing.
+
+/// Parses `input` into records, handling quoted fields with embedded separators,
+/// quotes and line breaks. Empty lines are skipped.
+pub fn parse(input: &str) -> eyre::Result<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            '"' => eyre::bail!("Unexpected quote in unquoted field on line {line}"),
+            '\n' if in_quotes => {
+                line += 1;
+                field.push(c);
+            }
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                line += 1;
+                record.push(std::mem::take(&mut field));
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        eyre::bail!("Unterminated quoted field on line {line}");
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Appends one record to `out`, quoting fields where necessary.
+pub fn write_record<I, S>(out: &mut String, fields: I)
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_tricky_fields() {
+        let records = vec![
+            vec!["name", "note"],
+            vec!["Solar Panel, roof", "says \"hi\""],
+            vec!["multi\nline", ""],
+        ];
+        let mut out = String::new();
+        for record in &records {
+            write_record(&mut out, record);
+        }
+        assert_eq!(parse(&out).unwrap(), records);
+    }
+
+    #[test]
+    fn handles_crlf_and_blank_lines() {
+        let parsed = parse("a,b\r\n\r\nc,d").unwrap();
+        assert_eq!(parsed, vec![vec!["a", "b"], vec!["c", "d"]]);
+    }
+
+    #[test]
+    fn rejects_unterminated_quote() {
+        assert!(parse("a,\"b\n").is_err());
+    }
+}
@@ -0,0 +1,548 @@
+//! Differences between two simulations, structurally and as text.
+//!
+//! Devices are matched by name, ignoring case like [`crate::simulation::check_unique_names`]
+//! does. Sources which aren't valid simulations, like a draft with errors, can still be
+//! compared line by line.
+
+use jiff::Timestamp;
+
+use crate::simulation::{Device, DeviceType, Simulation};
+
+/// Lines of context around the changes of a unified diff.
+const CONTEXT: usize = 3;
+
+/// Most lines compared pairwise for the unified diff, beyond that the changed part is
+/// shown as removed and added entirely.
+const MAX_COMPARED_LINES: usize = 4_000_000;
+
+/// A value which isn't the same on both sides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changed<T> {
+    pub old: T,
+    pub new: T,
+}
+
+impl<T: PartialEq> Changed<T> {
+    fn of(old: T, new: T) -> Option<Self> {
+        (old != new).then_some(Self { old, new })
+    }
+}
+
+/// A device parameter which differs, `None` on a side whose device type doesn't have it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterChange {
+    pub name: &'static str,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum DeviceChange<'a> {
+    Unchanged(&'a Device),
+    Added(&'a Device),
+    Removed(&'a Device),
+    /// Devices with the same name whose parameters differ, or just the case of the name.
+    Changed {
+        old: &'a Device,
+        new: &'a Device,
+        parameters: Vec<ParameterChange>,
+    },
+}
+
+/// What changed from one simulation to another.
+#[derive(Debug)]
+pub struct SimulationDiff<'a> {
+    pub start_time: Option<Changed<Timestamp>>,
+    pub end_time: Option<Changed<Timestamp>>,
+    /// Devices of the old simulation in their order, followed by those added.
+    pub devices: Vec<DeviceChange<'a>>,
+}
+
+pub fn simulations<'a>(old: &'a Simulation, new: &'a Simulation) -> SimulationDiff<'a> {
+    let find = |devices: &'a [Device], device: &Device| {
+        devices
+            .iter()
+            .find(|other| other.name().eq_ignore_case(device.name()))
+    };
+    let mut devices: Vec<_> = old
+        .devices()
+        .iter()
+        .map(|device| match find(new.devices(), device) {
+            None => DeviceChange::Removed(device),
+            Some(other) => {
+                let parameters = parameter_changes(device, other);
+                if parameters.is_empty() {
+                    DeviceChange::Unchanged(device)
+                } else {
+                    DeviceChange::Changed {
+                        old: device,
+                        new: other,
+                        parameters,
+                    }
+                }
+            }
+        })
+        .collect();
+    devices.extend(
+        new.devices()
+            .iter()
+            .filter(|device| find(old.devices(), device).is_none())
+            .map(DeviceChange::Added),
+    );
+    SimulationDiff {
+        start_time: Changed::of(old.start_time(), new.start_time()),
+        end_time: Changed::of(old.end_time(), new.end_time()),
+        devices,
+    }
+}
+
+/// Parameters of a device by their name in the JSON source, the name included.
+fn parameters(device: &Device) -> Vec<(&'static str, String)> {
+    let mut parameters = vec![("name", device.name().as_str().to_string())];
+    match device.device_type() {
+        DeviceType::SolarPanel => parameters.push(("type", "SolarPanel".to_string())),
+        DeviceType::StableDevice { produces } => {
+            parameters.push(("type", "StableDevice".to_string()));
+            parameters.push(("produces", produces.to_string()));
+        }
+        DeviceType::Store {
+            max_charge_per_tick,
+            max_capacity,
+        } => {
+            parameters.push(("type", "Store".to_string()));
+            parameters.push(("maxChargePerTick", max_charge_per_tick.to_string()));
+            parameters.push(("maxCapacity", max_capacity.to_string()));
+        }
+    }
+    parameters
+}
+
+fn parameter_changes(old: &Device, new: &Device) -> Vec<ParameterChange> {
+    let old = parameters(old);
+    let new = parameters(new);
+    let value = |parameters: &[(&str, String)], name: &str| {
+        parameters
+            .iter()
+            .find(|(other, _)| *other == name)
+            .map(|(_, value)| value.clone())
+    };
+    let added = new.iter().filter(|(name, _)| value(&old, name).is_none());
+    old.iter()
+        .chain(added)
+        .filter_map(|(name, _)| {
+            let change = ParameterChange {
+                name,
+                old: value(&old, name),
+                new: value(&new, name),
+            };
+            (change.old != change.new).then_some(change)
+        })
+        .collect()
+}
+
+/// One line of a device, with all its parameters.
+fn describe(device: &Device) -> String {
+    let mut parameters = parameters(device).into_iter();
+    let (_, name) = parameters.next().unwrap_or_default();
+    let rest: Vec<_> = parameters
+        .map(|(parameter, value)| match parameter {
+            "type" => value,
+            _ => format!("{parameter} {value}"),
+        })
+        .collect();
+    format!("{name}: {}", rest.join(", "))
+}
+
+/// How a row of the side-by-side view differs between the sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowKind {
+    Same,
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A row of the side-by-side view, a side is empty where it has nothing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub old: String,
+    pub new: String,
+    pub kind: RowKind,
+}
+
+impl Row {
+    fn new(old: String, new: String, kind: RowKind) -> Self {
+        Self { old, new, kind }
+    }
+}
+
+impl SimulationDiff<'_> {
+    /// Whether both simulations are the same.
+    pub fn is_empty(&self) -> bool {
+        self.start_time.is_none()
+            && self.end_time.is_none()
+            && self
+                .devices
+                .iter()
+                .all(|device| matches!(device, DeviceChange::Unchanged(_)))
+    }
+
+    /// Both simulations side by side, unchanged devices on a single row.
+    ///
+    /// `old` is the simulation compared, for what's the same on both sides.
+    pub fn rows(&self, old: &Simulation) -> Vec<Row> {
+        let time =
+            |label: &str, changed: &Option<Changed<Timestamp>>, same: Timestamp| match changed {
+                Some(Changed { old, new }) => Row::new(
+                    format!("{label} {old}"),
+                    format!("{label} {new}"),
+                    RowKind::Changed,
+                ),
+                None => Row::new(
+                    format!("{label} {same}"),
+                    format!("{label} {same}"),
+                    RowKind::Same,
+                ),
+            };
+        let mut rows = vec![
+            time("Start", &self.start_time, old.start_time()),
+            time("End", &self.end_time, old.end_time()),
+        ];
+        for device in &self.devices {
+            match device {
+                DeviceChange::Unchanged(device) => {
+                    rows.push(Row::new(describe(device), describe(device), RowKind::Same));
+                }
+                DeviceChange::Added(device) => {
+                    rows.push(Row::new(String::new(), describe(device), RowKind::Added));
+                }
+                DeviceChange::Removed(device) => {
+                    rows.push(Row::new(describe(device), String::new(), RowKind::Removed));
+                }
+                DeviceChange::Changed {
+                    old,
+                    new,
+                    parameters,
+                } => {
+                    rows.push(Row::new(
+                        format!("{}:", old.name().as_str()),
+                        format!("{}:", new.name().as_str()),
+                        RowKind::Same,
+                    ));
+                    rows.extend(parameters.iter().map(|change| {
+                        let side = |value: &Option<String>| {
+                            value
+                                .as_ref()
+                                .map(|value| format!("    {} {value}", change.name))
+                                .unwrap_or_default()
+                        };
+                        let kind = match (&change.old, &change.new) {
+                            (None, _) => RowKind::Added,
+                            (_, None) => RowKind::Removed,
+                            _ => RowKind::Changed,
+                        };
+                        Row::new(side(&change.old), side(&change.new), kind)
+                    }));
+                }
+            }
+        }
+        rows
+    }
+}
+
+/// Two simulation sources compared, for showing side by side or as a unified diff.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub old_label: String,
+    pub new_label: String,
+    /// `None` if either source isn't a valid simulation.
+    pub rows: Option<Vec<Row>>,
+    /// Empty if the sources are the same apart from formatting.
+    pub unified: String,
+    /// Whether the simulations are the same, even if their sources aren't.
+    pub same: bool,
+}
+
+pub fn compare(old_label: String, old: &str, new_label: String, new: &str) -> Comparison {
+    let parse = |source: &str| serde_json::from_str::<Simulation>(source).ok();
+    let (rows, same) = match (parse(old), parse(new)) {
+        (Some(old), Some(new)) => {
+            let diff = simulations(&old, &new);
+            (Some(diff.rows(&old)), diff.is_empty())
+        }
+        _ => (None, false),
+    };
+    // formatted alike, so only changed values show up
+    let pretty = |source: &str| {
+        serde_json::from_str::<serde_json::Value>(source)
+            .ok()
+            .and_then(|json| serde_json::to_string_pretty(&json).ok())
+            .unwrap_or_else(|| source.to_string())
+    };
+    let unified = unified(&old_label, &pretty(old), &new_label, &pretty(new));
+    let same = same || unified.is_empty();
+    Comparison {
+        old_label,
+        new_label,
+        rows,
+        unified,
+        same,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Same(usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// The lines of `old` and `new` which differ, as `diff -u` prints them.
+///
+/// Empty if both are the same.
+pub fn unified(old_label: &str, old: &str, new_label: &str, new: &str) -> String {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+    let ops = line_ops(&old, &new);
+
+    // positions in both texts before each operation
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for op in &ops {
+        positions.push((i, j));
+        match op {
+            Op::Same(_) => (i, j) = (i + 1, j + 1),
+            Op::Removed(_) => i += 1,
+            Op::Added(_) => j += 1,
+        }
+    }
+    positions.push((i, j));
+
+    let changes: Vec<usize> = (0..ops.len())
+        .filter(|&k| !matches!(ops[k], Op::Same(_)))
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+    let mut out = format!("--- {old_label}\n+++ {new_label}\n");
+    let mut first = 0;
+    while first < changes.len() {
+        // changes close enough to share their context go into one hunk
+        let mut last = first;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * CONTEXT + 1 {
+            last += 1;
+        }
+        let from = changes[first].saturating_sub(CONTEXT);
+        let to = (changes[last] + CONTEXT + 1).min(ops.len());
+        let (old_start, new_start) = positions[from];
+        let (old_end, new_end) = positions[to];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+        for op in &ops[from..to] {
+            let (prefix, line) = match *op {
+                Op::Same(i) => (' ', old[i]),
+                Op::Removed(i) => ('-', old[i]),
+                Op::Added(j) => ('+', new[j]),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            out.push('\n');
+        }
+        first = last + 1;
+    }
+    out
+}
+
+/// Start and length of a hunk, the line before it if it's empty like `diff` does.
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Turns `old` into `new` keeping their longest common subsequence of lines.
+fn line_ops(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<_> = (0..prefix).map(Op::Same).collect();
+    if a.len().saturating_mul(b.len()) > MAX_COMPARED_LINES {
+        ops.extend((0..a.len()).map(|i| Op::Removed(prefix + i)));
+        ops.extend((0..b.len()).map(|j| Op::Added(prefix + j)));
+    } else {
+        // lengths[i][j] is the longest common subsequence of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lengths = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lengths[i * width + j] = if a[i] == b[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                ops.push(Op::Same(prefix + i));
+                (i, j) = (i + 1, j + 1);
+            } else if j == b.len()
+                || (i < a.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+            {
+                ops.push(Op::Removed(prefix + i));
+                i += 1;
+            } else {
+                ops.push(Op::Added(prefix + j));
+                j += 1;
+            }
+        }
+    }
+    ops.extend((old.len() - suffix..old.len()).map(Op::Same));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulation(end: &str, devices: &str) -> String {
+        format!(r#"{{"startTime":"2024-01-01T00:00:00Z","endTime":"{end}","devices":[{devices}]}}"#)
+    }
+
+    #[test]
+    fn devices_are_matched_by_name() {
+        let old = simulation(
+            "2024-01-02T00:00:00Z",
+            r#"{"name":"Roof","type":"SolarPanel"},
+               {"name":"Fridge","type":"StableDevice","produces":-5},
+               {"name":"Battery","type":"Store","maxChargePerTick":5,"maxCapacity":100}"#,
+        );
+        let new = simulation(
+            "2024-01-03T00:00:00Z",
+            r#"{"name":"battery","type":"Store","maxChargePerTick":5,"maxCapacity":100},
+               {"name":"Fridge","type":"SolarPanel"},
+               {"name":"Heater","type":"StableDevice","produces":-20}"#,
+        );
+        let old: Simulation = serde_json::from_str(&old).unwrap();
+        let new: Simulation = serde_json::from_str(&new).unwrap();
+        let diff = simulations(&old, &new);
+        assert!(diff.start_time.is_none());
+        assert!(diff.end_time.is_some());
+        assert!(!diff.is_empty());
+
+        let kinds: Vec<_> = diff
+            .devices
+            .iter()
+            .map(|change| match change {
+                DeviceChange::Unchanged(d) => ("unchanged", d.name().as_str()),
+                DeviceChange::Added(d) => ("added", d.name().as_str()),
+                DeviceChange::Removed(d) => ("removed", d.name().as_str()),
+                DeviceChange::Changed { old, .. } => ("changed", old.name().as_str()),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("removed", "Roof"),
+                ("changed", "Fridge"),
+                ("changed", "Battery"),
+                ("added", "Heater"),
+            ]
+        );
+        let DeviceChange::Changed { parameters, .. } = &diff.devices[1] else {
+            unreachable!()
+        };
+        assert_eq!(
+            parameters,
+            &[
+                ParameterChange {
+                    name: "type",
+                    old: Some("StableDevice".to_string()),
+                    new: Some("SolarPanel".to_string()),
+                },
+                ParameterChange {
+                    name: "produces",
+                    old: Some("-5".to_string()),
+                    new: None,
+                },
+            ]
+        );
+
+        let rows = diff.rows(&old);
+        assert_eq!(rows[1].kind, RowKind::Changed);
+        assert_eq!(
+            rows[2],
+            Row::new(
+                "Roof: SolarPanel".to_string(),
+                String::new(),
+                RowKind::Removed
+            )
+        );
+        assert_eq!(
+            rows.last().unwrap(),
+            &Row::new(
+                String::new(),
+                "Heater: StableDevice, produces -20".to_string(),
+                RowKind::Added
+            )
+        );
+        assert!(simulations(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn unified_diff_has_hunks_with_context() {
+        let old: String = (1..=20).map(|i| format!("{i}\n")).collect();
+        let new: String = (1..=21)
+            .filter(|i| *i != 18)
+            .map(|i| match i {
+                2 => "two\n".to_string(),
+                i => format!("{i}\n"),
+            })
+            .collect();
+        assert_eq!(
+            unified("a", &old, "b", &new),
+            "--- a\n+++ b\n\
+             @@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+             @@ -15,6 +15,6 @@\n 15\n 16\n 17\n-18\n 19\n 20\n+21\n"
+        );
+        assert_eq!(unified("a", &old, "b", &old), "");
+        assert_eq!(
+            unified("a", "", "b", "x"),
+            "--- a\n+++ b\n@@ -0,0 +1 @@\n+x\n"
+        );
+    }
+
+    #[test]
+    fn invalid_drafts_are_compared_as_text() {
+        let old = simulation("2024-01-02T00:00:00Z", "");
+        let comparison = compare("run".into(), &old, "draft".into(), "{\"devices\":");
+        assert!(comparison.rows.is_none());
+        assert!(!comparison.same);
+        assert!(comparison.unified.contains("+{\"devices\":"));
+
+        // only formatted differently
+        let pretty =
+            serde_json::to_string_pretty(&serde_json::from_str::<serde_json::Value>(&old).unwrap())
+                .unwrap();
+        let comparison = compare("run".into(), &old, "draft".into(), &pretty);
+        assert!(comparison.same);
+        assert!(comparison
+            .rows
+            .unwrap()
+            .iter()
+            .all(|row| row.kind == RowKind::Same));
+    }
+}
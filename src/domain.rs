@@ -1,12 +1,155 @@
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+use std::fmt;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+
+/// Longest device name allowed, in characters.
+pub const MAX_DEVICE_NAME_LEN: usize = 64;
+
+#[derive(serde::Serialize, Debug, Clone)]
 pub struct DeviceName(String);
 
+/// Why a string isn't a valid [`DeviceName`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidDeviceName {
+    Empty,
+    TooLong,
+    Character(char),
+}
+
+impl fmt::Display for InvalidDeviceName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidDeviceName::Empty => write!(f, "device name is empty"),
+            InvalidDeviceName::TooLong => write!(
+                f,
+                "device name is longer than {MAX_DEVICE_NAME_LEN} characters"
+            ),
+            InvalidDeviceName::Character(c) => write!(
+                f,
+                "device name contains {c:?}, only letters, digits, spaces and punctuation are allowed"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvalidDeviceName {}
+
 impl DeviceName {
-    pub fn new(name: String) -> Self {
-        Self(name)
+    /// Checks `name` with surrounding whitespace removed.
+    pub fn try_new(name: impl Into<String>) -> Result<Self, InvalidDeviceName> {
+        let name = name.into();
+        let trimmed = name.trim();
+        if trimmed.is_empty() {
+            return Err(InvalidDeviceName::Empty);
+        }
+        if trimmed.chars().count() > MAX_DEVICE_NAME_LEN {
+            return Err(InvalidDeviceName::TooLong);
+        }
+        if let Some(c) = trimmed
+            .chars()
+            .find(|c| !(c.is_alphanumeric() || *c == ' ' || c.is_ascii_punctuation()))
+        {
+            return Err(InvalidDeviceName::Character(c));
+        }
+        Ok(Self(if trimmed.len() == name.len() {
+            name
+        } else {
+            trimmed.to_string()
+        }))
+    }
+
+    /// A name the server sent, only trimmed.
+    ///
+    /// Servers may know devices by names [`DeviceName::try_new`] rejects, which still
+    /// have to be shown and used in requests.
+    fn from_server(name: String) -> Self {
+        let trimmed = name.trim();
+        Self(if trimmed.len() == name.len() {
+            name
+        } else {
+            trimmed.to_string()
+        })
+    }
+
+    /// Deserializes a name with [`DeviceName::from_server`], for `deserialize_with` on
+    /// types received from the server.
+    pub fn deserialize_from_server<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(de).map(Self::from_server)
+    }
+
+    /// Like [`DeviceName::deserialize_from_server`] for a list of names.
+    pub fn deserialize_all_from_server<'de, D>(de: D) -> Result<Vec<Self>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let names = Vec::<String>::deserialize(de)?;
+        Ok(names.into_iter().map(Self::from_server).collect())
     }
 
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Whether both names are the same apart from case, which makes them ambiguous.
+    pub fn eq_ignore_case(&self, other: &DeviceName) -> bool {
+        self.0.to_lowercase() == other.0.to_lowercase()
+    }
+}
+
+impl<'de> Deserialize<'de> for DeviceName {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(de)?;
+        DeviceName::try_new(name.as_str())
+            .map_err(|e| D::Error::custom(format!("invalid device name {name:?}: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_trimmed_and_checked() {
+        assert_eq!(
+            DeviceName::try_new("  Solar Panel (roof) #1 ")
+                .unwrap()
+                .as_str(),
+            "Solar Panel (roof) #1"
+        );
+        assert_eq!(
+            DeviceName::try_new("Wärmepumpe").unwrap().as_str(),
+            "Wärmepumpe"
+        );
+        assert_eq!(
+            DeviceName::try_new(" \t").unwrap_err(),
+            InvalidDeviceName::Empty
+        );
+        assert_eq!(
+            DeviceName::try_new("x".repeat(MAX_DEVICE_NAME_LEN + 1)).unwrap_err(),
+            InvalidDeviceName::TooLong
+        );
+        assert!(DeviceName::try_new("ä".repeat(MAX_DEVICE_NAME_LEN)).is_ok());
+        assert_eq!(
+            DeviceName::try_new("Fryer\nkitchen").unwrap_err(),
+            InvalidDeviceName::Character('\n')
+        );
+    }
+
+    #[test]
+    fn deserializing_reports_the_name() {
+        let e = serde_json::from_str::<DeviceName>(r#""""#).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid device name \"\": device name is empty"
+        );
+        let name: DeviceName = serde_json::from_str(r#"" Battery ""#).unwrap();
+        assert_eq!(name.as_str(), "Battery");
+    }
 }
@@ -0,0 +1,538 @@
+//! Exporting simulation results to files for further analysis.
+
+use std::io::{Seek, Write};
+use std::path::Path;
+
+use eyre::WrapErr;
+use futures::stream::{self, StreamExt};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::api;
+use crate::domain::DeviceName;
+use crate::table::Table;
+
+/// How many simulations are fetched at once when exporting all, each fetching up to
+/// [`api::LOG_CONCURRENCY`] device logs at a time.
+const SIMULATION_CONCURRENCY: usize = 2;
+
+/// File formats simulations can be exported to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl ExportFormat {
+    pub const ALL: &'static [ExportFormat] = &[
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => "Parquet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Everything known about a finished simulation.
+#[derive(Debug, Clone)]
+pub struct SimulationData {
+    pub id: Uuid,
+    pub timestamp: Option<jiff::Timestamp>,
+    pub simulation: api::Simulation,
+    /// The raw log of every device, in the order of `simulation.devices`.
+    pub logs: Vec<(DeviceName, String)>,
+}
+
+impl SimulationData {
+    /// Fetches the report and all device logs of simulation `id`.
+    pub async fn fetch(
+        client: &api::Client,
+        id: Uuid,
+        timestamp: Option<jiff::Timestamp>,
+    ) -> eyre::Result<Self> {
+        let simulation = client
+            .fetch_simulation(id)
+            .await
+            .wrap_err_with(|| format!("Failed fetching simulation {id}"))?;
+
+        let mut fetched = client
+            .fetch_all_device_logs(id, &simulation.devices)
+            .collect::<Vec<_>>()
+            .await;
+        // they arrive in no particular order
+        let logs = simulation
+            .devices
+            .iter()
+            .filter_map(|device| {
+                let i = fetched
+                    .iter()
+                    .position(|(other, _)| other.as_str() == device.as_str())?;
+                Some(fetched.swap_remove(i))
+            })
+            .map(|(device, log)| {
+                let log =
+                    log.wrap_err_with(|| format!("Failed fetching log of {}", device.as_str()))?;
+                Ok((device, log))
+            })
+            .collect::<eyre::Result<_>>()?;
+
+        Ok(Self {
+            id,
+            timestamp,
+            simulation,
+            logs,
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let logs = self
+            .logs
+            .iter()
+            .map(|(device, log)| {
+                let log = crate::table::parse_json_or_lines(log)
+                    .unwrap_or_else(|| serde_json::Value::String(log.clone()));
+                (device.as_str().to_string(), log)
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        json!({
+            "id": self.id,
+            "timestamp": self.timestamp,
+            "result": self.simulation.res.result,
+            "devices": self.simulation.devices,
+            "logs": logs,
+        })
+    }
+
+    /// Flattens the simulation into one row per log entry of every device.
+    ///
+    /// Logs which aren't structured end up in a `log` column, devices without log entries
+    /// still get a row so the device list is complete.
+    pub fn to_table(&self) -> Table {
+        const FIXED: [&str; 4] = ["simulation", "timestamp", "result", "device"];
+
+        let parsed = self
+            .logs
+            .iter()
+            .map(|(device, log)| {
+                let table = Table::parse_log(log).unwrap_or_else(|| {
+                    let mut table = Table::new(vec!["log".to_string()]);
+                    if !log.trim().is_empty() {
+                        table.rows.push(vec![log.clone()]);
+                    }
+                    table
+                });
+                (device, table)
+            })
+            .collect::<Vec<_>>();
+
+        let mut table = Table::new(FIXED.map(String::from).to_vec());
+        for (_, log) in &parsed {
+            for column in &log.columns {
+                if !table.columns.contains(column) {
+                    table.columns.push(column.clone());
+                }
+            }
+        }
+
+        let fixed = [
+            self.id.to_string(),
+            self.timestamp.map(|t| t.to_string()).unwrap_or_default(),
+            self.simulation.res.result.to_string(),
+        ];
+        for (device, log) in parsed {
+            let row = |cells: &[String]| {
+                let mut row = fixed.to_vec();
+                row.push(device.as_str().to_string());
+                row.resize(table.columns.len(), String::new());
+                for (column, cell) in log.columns.iter().zip(cells) {
+                    let i = table.columns.iter().position(|c| c == column).unwrap();
+                    row[i] = cell.clone();
+                }
+                row
+            };
+
+            if log.rows.is_empty() {
+                table.rows.push(row(&[]));
+            }
+            for cells in &log.rows {
+                table.rows.push(row(cells));
+            }
+        }
+
+        table
+    }
+}
+
+/// Writes one simulation to `out`.
+pub fn write(
+    data: &SimulationData,
+    format: ExportFormat,
+    out: impl Write + Send,
+) -> eyre::Result<()> {
+    write_many(std::slice::from_ref(data), format, out)
+}
+
+/// Writes several simulations to `out`, as one table or one JSON array.
+fn write_many(
+    data: &[SimulationData],
+    format: ExportFormat,
+    mut out: impl Write + Send,
+) -> eyre::Result<()> {
+    match format {
+        ExportFormat::Csv => out.write_all(merge(data).to_csv().as_bytes())?,
+        ExportFormat::Json => {
+            let json = match data {
+                [single] => single.to_json(),
+                many => many.iter().map(SimulationData::to_json).collect(),
+            };
+            serde_json::to_writer_pretty(&mut out, &json)?;
+        }
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => parquet_file::write(&merge(data), out)?,
+    }
+    Ok(())
+}
+
+fn merge(data: &[SimulationData]) -> Table {
+    let mut merged = Table::default();
+    for table in data.iter().map(SimulationData::to_table) {
+        for column in &table.columns {
+            if !merged.columns.contains(column) {
+                merged.columns.push(column.clone());
+            }
+        }
+        for cells in table.rows {
+            let mut row = vec![String::new(); merged.columns.len()];
+            for (column, cell) in table.columns.iter().zip(cells) {
+                let i = merged.columns.iter().position(|c| c == column).unwrap();
+                row[i] = cell;
+            }
+            merged.rows.push(row);
+        }
+    }
+    // rows of earlier simulations are shorter if later ones added columns
+    for row in &mut merged.rows {
+        row.resize(merged.columns.len(), String::new());
+    }
+    merged
+}
+
+/// Fetches one simulation and writes it to `path`.
+pub async fn export_simulation(
+    client: &api::Client,
+    overview: &api::SimulationOverview,
+    format: ExportFormat,
+    path: &Path,
+) -> eyre::Result<()> {
+    let data = SimulationData::fetch(client, overview.id, Some(overview.timestamp)).await?;
+    let file = std::fs::File::create(path)
+        .wrap_err_with(|| format!("Failed creating {}", path.display()))?;
+    let mut out = std::io::BufWriter::new(file);
+    write(&data, format, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Fetches every simulation and writes them into a zip archive at `path`.
+///
+/// The archive contains an `index.csv` listing all simulations, one file per simulation
+/// in `simulations/` and all of them combined in `all.<extension>`. `progress` is called
+/// with the number of simulations fetched so far.
+pub async fn export_all(
+    client: &api::Client,
+    overviews: &[api::SimulationOverview],
+    format: ExportFormat,
+    path: &Path,
+    mut progress: impl FnMut(usize),
+) -> eyre::Result<()> {
+    let mut data = Vec::with_capacity(overviews.len());
+    let mut fetched = stream::iter(overviews)
+        .map(|overview| SimulationData::fetch(client, overview.id, Some(overview.timestamp)))
+        .buffered(SIMULATION_CONCURRENCY);
+    while let Some(sim) = fetched.next().await {
+        data.push(sim?);
+        progress(data.len());
+    }
+
+    let file = std::fs::File::create(path)
+        .wrap_err_with(|| format!("Failed creating {}", path.display()))?;
+    write_archive(&data, format, file)
+}
+
+fn write_archive(
+    data: &[SimulationData],
+    format: ExportFormat,
+    out: impl Write + Seek,
+) -> eyre::Result<()> {
+    let mut zip = zip::ZipWriter::new(out);
+    let options = zip::write::SimpleFileOptions::default();
+
+    let mut index = Table::new(vec![
+        "simulation".into(),
+        "timestamp".into(),
+        "result".into(),
+    ]);
+    for sim in data {
+        index.rows.push(vec![
+            sim.id.to_string(),
+            sim.timestamp.map(|t| t.to_string()).unwrap_or_default(),
+            sim.simulation.res.result.to_string(),
+        ]);
+    }
+    zip.start_file("index.csv", options)?;
+    zip.write_all(index.to_csv().as_bytes())?;
+
+    // parquet needs to know the total size up front, so every file goes through a buffer
+    let mut buf = Vec::new();
+    for sim in data {
+        buf.clear();
+        write(sim, format, &mut buf)?;
+        zip.start_file(
+            format!("simulations/{}.{}", sim.id, format.extension()),
+            options,
+        )?;
+        zip.write_all(&buf)?;
+    }
+
+    buf.clear();
+    write_many(data, format, &mut buf)?;
+    zip.start_file(format!("all.{}", format.extension()), options)?;
+    zip.write_all(&buf)?;
+
+    zip.finish()?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_file {
+    use std::io::Write;
+    use std::sync::Arc;
+
+    use parquet::basic::{LogicalType, Repetition, Type as PhysicalType};
+    use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::types::Type;
+
+    use crate::table::Table;
+
+    #[derive(Clone, Copy)]
+    enum Column {
+        Int,
+        Float,
+        Text,
+    }
+
+    /// Picks the narrowest type every non-empty cell of column `i` fits in.
+    fn column_type(table: &Table, i: usize) -> Column {
+        let mut cells = table
+            .rows
+            .iter()
+            .map(|row| &row[i])
+            .filter(|c| !c.is_empty());
+        if cells.clone().all(|c| c.parse::<i64>().is_ok()) {
+            Column::Int
+        } else if cells.all(|c| c.parse::<f64>().is_ok()) {
+            Column::Float
+        } else {
+            Column::Text
+        }
+    }
+
+    /// Writes `table` as a single row group, empty cells become nulls.
+    pub fn write(table: &Table, out: impl Write + Send) -> eyre::Result<()> {
+        let types = (0..table.columns.len())
+            .map(|i| column_type(table, i))
+            .collect::<Vec<_>>();
+
+        let fields = table
+            .columns
+            .iter()
+            .zip(&types)
+            .map(|(name, ty)| {
+                let builder = match ty {
+                    Column::Int => Type::primitive_type_builder(name, PhysicalType::INT64),
+                    Column::Float => Type::primitive_type_builder(name, PhysicalType::DOUBLE),
+                    Column::Text => Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY)
+                        .with_logical_type(Some(LogicalType::String)),
+                };
+                Ok(Arc::new(
+                    builder.with_repetition(Repetition::OPTIONAL).build()?,
+                ))
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let schema = Type::group_type_builder("simulation")
+            .with_fields(fields)
+            .build()?;
+
+        let mut writer = SerializedFileWriter::new(
+            out,
+            Arc::new(schema),
+            Arc::new(WriterProperties::builder().build()),
+        )?;
+        let mut row_group = writer.next_row_group()?;
+        let mut i = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            let cells = table.rows.iter().map(|row| &row[i]);
+            let levels = cells
+                .clone()
+                .map(|c| i16::from(!c.is_empty()))
+                .collect::<Vec<_>>();
+            let present = cells.filter(|c| !c.is_empty());
+
+            match types[i] {
+                Column::Int => {
+                    let values = present.map(|c| c.parse().unwrap()).collect::<Vec<i64>>();
+                    column
+                        .typed::<Int64Type>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+                Column::Float => {
+                    let values = present.map(|c| c.parse().unwrap()).collect::<Vec<f64>>();
+                    column
+                        .typed::<DoubleType>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+                Column::Text => {
+                    let values = present
+                        .map(|c| ByteArray::from(c.as_str()))
+                        .collect::<Vec<_>>();
+                    column
+                        .typed::<ByteArrayType>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+            }
+            column.close()?;
+            i += 1;
+        }
+        row_group.close()?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> SimulationData {
+        SimulationData {
+            id: Uuid::nil(),
+            timestamp: None,
+            simulation: api::Simulation {
+                devices: vec![
+                    DeviceName::try_new("Battery 1").unwrap(),
+                    DeviceName::try_new("Fryer").unwrap(),
+                ],
+                res: api::Report { result: 42 },
+            },
+            logs: vec![
+                (
+                    DeviceName::try_new("Battery 1").unwrap(),
+                    "{\"tick\":1,\"charge\":5}\n{\"tick\":2,\"charge\":7}".into(),
+                ),
+                (DeviceName::try_new("Fryer").unwrap(), String::new()),
+            ],
+        }
+    }
+
+    #[test]
+    fn table_has_row_per_entry_and_device() {
+        let table = data().to_table();
+        assert_eq!(
+            table.columns,
+            [
+                "simulation",
+                "timestamp",
+                "result",
+                "device",
+                "charge",
+                "tick",
+                "log"
+            ]
+        );
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.rows[1][3..6], ["Battery 1", "7", "2"]);
+        assert_eq!(table.rows[2][3], "Fryer");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_round_trips() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::Field;
+
+        let path =
+            std::env::temp_dir().join(format!("ha-ui-export-test-{}.parquet", std::process::id()));
+        write(
+            &data(),
+            ExportFormat::Parquet,
+            std::fs::File::create(&path).unwrap(),
+        )
+        .unwrap();
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+
+        let columns = rows[1]
+            .get_column_iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(columns, data().to_table().columns);
+        let cell = |row: usize, column: &str| {
+            rows[row]
+                .get_column_iter()
+                .find(|(name, _)| *name == column)
+                .map(|(_, field)| field.clone())
+                .unwrap()
+        };
+        assert_eq!(cell(1, "device"), Field::Str("Battery 1".into()));
+        assert_eq!(cell(1, "charge"), Field::Long(7));
+        assert_eq!(cell(1, "result"), Field::Long(42));
+        assert_eq!(cell(2, "device"), Field::Str("Fryer".into()));
+        assert_eq!(cell(2, "tick"), Field::Null);
+    }
+
+    #[test]
+    fn archive_contains_all_files() {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        write_archive(&[data()], ExportFormat::Json, &mut buf).unwrap();
+        let archive = zip::ZipArchive::new(buf).unwrap();
+        let mut names = archive.file_names().collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            [
+                "all.json",
+                "index.csv",
+                "simulations/00000000-0000-0000-0000-000000000000.json"
+            ]
+        );
+    }
+}
@@ -0,0 +1,88 @@
+/// How many snapshots are kept for undo.
+const MAX_SNAPSHOTS: usize = 100;
+
+/// Undo/redo history of a text buffer, stored as full snapshots.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<String>,
+    redo: Vec<String>,
+}
+
+impl History {
+    /// Records `previous`, the text before an edit.
+    ///
+    /// With `coalesce` the edit is merged into the last recorded one, so typing a word
+    /// is undone in one step.
+    pub fn record(&mut self, previous: String, coalesce: bool) {
+        self.redo.clear();
+        if coalesce && !self.undo.is_empty() {
+            return;
+        }
+        if self.undo.last() == Some(&previous) {
+            return;
+        }
+        if self.undo.len() == MAX_SNAPSHOTS {
+            self.undo.remove(0);
+        }
+        self.undo.push(previous);
+    }
+
+    /// Returns the text to restore, `current` becomes redoable.
+    pub fn undo(&mut self, current: String) -> Option<String> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// Returns the text to restore, `current` becomes undoable.
+    pub fn redo(&mut self, current: String) -> Option<String> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_redo_roundtrip() {
+        let mut history = History::default();
+        history.record("a".into(), false);
+        history.record("ab".into(), false);
+
+        assert_eq!(history.undo("abc".into()).as_deref(), Some("ab"));
+        assert_eq!(history.undo("ab".into()).as_deref(), Some("a"));
+        assert_eq!(history.undo("a".into()), None);
+        assert_eq!(history.redo("a".into()).as_deref(), Some("ab"));
+        assert_eq!(history.redo("ab".into()).as_deref(), Some("abc"));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let mut history = History::default();
+        history.record("a".into(), false);
+        history.undo("ab".into());
+        history.record("a".into(), false);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn coalesced_edits_undo_together() {
+        let mut history = History::default();
+        history.record("".into(), false);
+        history.record("w".into(), true);
+        history.record("wo".into(), true);
+        assert_eq!(history.undo("wor".into()).as_deref(), Some(""));
+    }
+}
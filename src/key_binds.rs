@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, HashMap};
+
+use cosmic::iced::keyboard::{key::Named, Key};
+use cosmic::widget::menu::key_bind::{KeyBind, Modifier};
+
+use crate::app::MenuAction;
+
+/// Modifiers in the order they are written in a binding.
+const MODIFIERS: [Modifier; 4] = [
+    Modifier::Super,
+    Modifier::Ctrl,
+    Modifier::Alt,
+    Modifier::Shift,
+];
+
+/// Named keys which can be used in a textual binding, next to single characters.
+const NAMED_KEYS: &[Named] = &[
+    Named::Enter,
+    Named::Tab,
+    Named::Space,
+    Named::Escape,
+    Named::Backspace,
+    Named::Delete,
+    Named::Insert,
+    Named::Home,
+    Named::End,
+    Named::PageUp,
+    Named::PageDown,
+    Named::ArrowUp,
+    Named::ArrowDown,
+    Named::ArrowLeft,
+    Named::ArrowRight,
+    Named::F1,
+    Named::F2,
+    Named::F3,
+    Named::F4,
+    Named::F5,
+    Named::F6,
+    Named::F7,
+    Named::F8,
+    Named::F9,
+    Named::F10,
+    Named::F11,
+    Named::F12,
+];
+
+/// The binding an action has when the user didn't configure one.
+pub fn default_binding(action: MenuAction) -> Option<&'static str> {
+    Some(match action {
+        MenuAction::OpenFile => "Ctrl+O",
+        MenuAction::Save => "Ctrl+S",
+        MenuAction::SaveAs => "Ctrl+Shift+S",
+        MenuAction::Quit => "Ctrl+Q",
+        MenuAction::Undo => "Ctrl+Z",
+        MenuAction::Redo => "Ctrl+Shift+Z",
+        MenuAction::FormatJson => "Ctrl+Shift+F",
+        MenuAction::Submit => "F9",
+        MenuAction::RefreshList => "F5",
+        MenuAction::NewFromTemplate => "Ctrl+N",
+        MenuAction::CopyOutput => "Ctrl+Shift+C",
+        MenuAction::NextSimulation => "Alt+ArrowDown",
+        MenuAction::PreviousSimulation => "Alt+ArrowUp",
+        MenuAction::ToggleSettings => "Ctrl+,",
+        MenuAction::CommandPalette => "Ctrl+P",
+        MenuAction::About
+        | MenuAction::OpenRecent(_)
+        | MenuAction::CopyAs(_)
+        | MenuAction::ImportCsv
+        | MenuAction::ExportResults
+        | MenuAction::ExportAll
+        | MenuAction::GenerateReport
+        | MenuAction::ClearCache => return None,
+    })
+}
+
+/// The binding of `action`, taking the user overrides into account.
+pub fn binding(action: MenuAction, overrides: &BTreeMap<String, String>) -> Option<&str> {
+    match overrides.get(action.id()) {
+        Some(binding) if binding.is_empty() => None,
+        Some(binding) => Some(binding),
+        None => default_binding(action),
+    }
+}
+
+/// Builds the key binding table from the defaults and the user overrides.
+///
+/// Bindings which can't be parsed are skipped, if two actions share a binding the one
+/// listed first in [`MenuAction::ALL`] wins.
+pub fn key_binds(overrides: &BTreeMap<String, String>) -> HashMap<KeyBind, MenuAction> {
+    let mut key_binds = HashMap::new();
+
+    for action in MenuAction::ALL {
+        if let Some(key_bind) = binding(action, overrides).and_then(|b| parse(b).ok()) {
+            key_binds.entry(key_bind).or_insert(action);
+        }
+    }
+
+    key_binds
+}
+
+/// Parses a binding like `Ctrl+Shift+P` or `F5`.
+pub fn parse(binding: &str) -> Result<KeyBind, String> {
+    let binding = binding.trim();
+    // the key itself may be a `+`, so split it off before looking at the modifiers
+    let (modifiers, key) = match binding.rsplit_once('+') {
+        Some((modifiers, "")) => (modifiers.strip_suffix('+').unwrap_or(modifiers), "+"),
+        Some((modifiers, key)) => (modifiers, key),
+        None => ("", binding),
+    };
+
+    let modifiers = modifiers
+        .split('+')
+        .filter(|m| !m.is_empty())
+        .map(|m| match m.trim().to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Ok(Modifier::Ctrl),
+            "shift" => Ok(Modifier::Shift),
+            "alt" => Ok(Modifier::Alt),
+            "super" | "logo" | "meta" => Ok(Modifier::Super),
+            _ => Err(format!("Unknown modifier \"{m}\"")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let modifiers = MODIFIERS
+        .into_iter()
+        .filter(|m| modifiers.contains(m))
+        .collect();
+
+    let key = parse_key(key.trim()).ok_or_else(|| format!("Unknown key \"{key}\""))?;
+
+    Ok(KeyBind { modifiers, key })
+}
+
+fn parse_key(key: &str) -> Option<Key> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(Key::Character(c.to_lowercase().to_string().into())),
+        _ => NAMED_KEYS
+            .iter()
+            .find(|named| format!("{named:?}").eq_ignore_ascii_case(key))
+            .map(|named| Key::Named(*named)),
+    }
+}
+
+/// Formats a binding in the format understood by [`parse`].
+pub fn format(key_bind: &KeyBind) -> String {
+    let mut ret = String::new();
+
+    for modifier in MODIFIERS {
+        if key_bind.modifiers.contains(&modifier) {
+            ret.push_str(&format!("{modifier:?}+"));
+        }
+    }
+
+    match &key_bind.key {
+        Key::Character(c) => ret.push_str(&c.to_uppercase()),
+        Key::Named(named) => ret.push_str(&format!("{named:?}")),
+        Key::Unidentified => ret.push('?'),
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_format_roundtrip() {
+        for binding in ["Ctrl+Shift+P", "F5", "Alt+ArrowDown", "Ctrl++", "Ctrl+,"] {
+            assert_eq!(format(&parse(binding).unwrap()), binding);
+        }
+    }
+
+    #[test]
+    fn parse_is_lenient() {
+        assert_eq!(
+            parse("shift+ctrl+p").unwrap(),
+            parse("Ctrl+Shift+P").unwrap()
+        );
+        assert_eq!(
+            parse(" control + enter ").unwrap(),
+            parse("Ctrl+Enter").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_rejects_garbage() {
+        assert!(parse("Hyper+P").is_err());
+        assert!(parse("Ctrl+Banana").is_err());
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let binds = key_binds(&BTreeMap::new());
+        let bound = MenuAction::ALL
+            .iter()
+            .filter(|action| default_binding(**action).is_some())
+            .count();
+        assert_eq!(binds.len(), bound);
+    }
+
+    #[test]
+    fn overrides_replace_and_unbind() {
+        let overrides = BTreeMap::from([
+            ("save".to_string(), "Ctrl+Shift+S".to_string()),
+            ("command-palette".to_string(), String::new()),
+        ]);
+        let binds = key_binds(&overrides);
+        assert_eq!(
+            binds.get(&parse("Ctrl+Shift+S").unwrap()),
+            Some(&MenuAction::Save)
+        );
+        assert!(!binds.contains_key(&parse("Ctrl+S").unwrap()));
+        assert!(!binds.values().any(|a| *a == MenuAction::CommandPalette));
+    }
+}
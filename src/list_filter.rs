@@ -0,0 +1,305 @@
+//! Searching, filtering and sorting the simulation list.
+
+use std::collections::{BTreeMap, HashMap};
+
+use jiff::civil::Date;
+use jiff::tz::TimeZone;
+use uuid::Uuid;
+
+use crate::api::{self, SimulationOverview};
+use crate::metadata::SimulationMeta;
+
+/// Orders the simulation list can be sorted in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SortOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+    ResultDescending,
+    ResultAscending,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 4] = [
+        SortOrder::NewestFirst,
+        SortOrder::OldestFirst,
+        SortOrder::ResultDescending,
+        SortOrder::ResultAscending,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::NewestFirst => "Newest first",
+            SortOrder::OldestFirst => "Oldest first",
+            SortOrder::ResultDescending => "Highest result first",
+            SortOrder::ResultAscending => "Lowest result first",
+        }
+    }
+}
+
+/// What the user is looking for in the simulation list.
+#[derive(Clone, Debug, Default)]
+pub struct ListFilter {
+    /// Whitespace separated terms, each matching an id prefix, part of a device name or
+    /// the metadata as described in [`SimulationMeta::matches`].
+    pub query: String,
+    /// First day shown, inclusive.
+    pub from: Option<Date>,
+    /// Last day shown, inclusive.
+    pub to: Option<Date>,
+    pub sort: SortOrder,
+    pub group_by_day: bool,
+}
+
+/// Simulations run on one day, `None` if the list isn't grouped.
+pub type Group<'a> = (Option<Date>, Vec<&'a SimulationOverview>);
+
+impl ListFilter {
+    /// Whether the reports of all simulations are needed, not just those on screen.
+    pub fn needs_details(&self) -> bool {
+        !self.query.trim().is_empty()
+            || matches!(
+                self.sort,
+                SortOrder::ResultDescending | SortOrder::ResultAscending
+            )
+    }
+
+    /// Whether `overview` matches, `details` are used for device names once fetched.
+    pub fn matches(
+        &self,
+        overview: &SimulationOverview,
+        details: Option<&api::Simulation>,
+        meta: Option<&SimulationMeta>,
+        tz: &TimeZone,
+    ) -> bool {
+        let date = overview.timestamp.to_zoned(tz.clone()).date();
+        if self.from.is_some_and(|from| date < from) || self.to.is_some_and(|to| date > to) {
+            return false;
+        }
+
+        let id = overview.id.to_string();
+        self.query.split_whitespace().all(|term| {
+            let term = term.to_lowercase();
+            id.starts_with(&term)
+                || details.is_some_and(|sim| {
+                    sim.devices
+                        .iter()
+                        .any(|device| device.as_str().to_lowercase().contains(&term))
+                })
+                || meta.is_some_and(|meta| meta.matches(&term))
+        })
+    }
+
+    /// The matching simulations in display order, grouped by day if enabled.
+    ///
+    /// Simulations whose result isn't known yet sort after all others.
+    pub fn apply<'a>(
+        &self,
+        simulations: &'a [SimulationOverview],
+        details: &HashMap<Uuid, api::Simulation>,
+        metadata: &BTreeMap<Uuid, SimulationMeta>,
+        tz: &TimeZone,
+    ) -> Vec<Group<'a>> {
+        let mut visible = simulations
+            .iter()
+            .filter(|sim| self.matches(sim, details.get(&sim.id), metadata.get(&sim.id), tz))
+            .collect::<Vec<_>>();
+
+        let result = |sim: &SimulationOverview| details.get(&sim.id).map(|d| d.res.result);
+        visible.sort_by(|a, b| match self.sort {
+            SortOrder::NewestFirst => b.timestamp.cmp(&a.timestamp),
+            SortOrder::OldestFirst => a.timestamp.cmp(&b.timestamp),
+            SortOrder::ResultDescending => match (result(a), result(b)) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+            SortOrder::ResultAscending => match (result(a), result(b)) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+        });
+
+        if !self.group_by_day {
+            return vec![(None, visible)];
+        }
+
+        let date = |sim: &SimulationOverview| sim.timestamp.to_zoned(tz.clone()).date();
+        // days follow the time order, or newest first when sorting by result
+        let oldest_first = self.sort == SortOrder::OldestFirst;
+        visible.sort_by(|a, b| {
+            let (a, b) = (date(a), date(b));
+            if oldest_first {
+                a.cmp(&b)
+            } else {
+                b.cmp(&a)
+            }
+        });
+
+        let mut groups: Vec<Group<'a>> = Vec::new();
+        for sim in visible {
+            let day = date(sim);
+            match groups.last_mut() {
+                Some((Some(last), sims)) if *last == day => sims.push(sim),
+                _ => groups.push((Some(day), vec![sim])),
+            }
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DeviceName;
+
+    /// Ids differing in their first digit, so prefixes tell them apart.
+    fn uuid(n: u128) -> Uuid {
+        Uuid::from_u128(n << 120)
+    }
+
+    fn overview(n: u128, timestamp: &str) -> SimulationOverview {
+        SimulationOverview {
+            id: uuid(n),
+            timestamp: timestamp.parse().unwrap(),
+        }
+    }
+
+    fn simulation(result: i64, devices: &[&str]) -> api::Simulation {
+        api::Simulation {
+            devices: devices
+                .iter()
+                .map(|name| DeviceName::try_new(*name).unwrap())
+                .collect(),
+            res: api::Report { result },
+        }
+    }
+
+    fn ids(groups: &[Group]) -> Vec<u128> {
+        groups
+            .iter()
+            .flat_map(|(_, sims)| sims.iter().map(|sim| sim.id.as_u128() >> 120))
+            .collect()
+    }
+
+    type Fixture = (
+        Vec<SimulationOverview>,
+        HashMap<Uuid, api::Simulation>,
+        BTreeMap<Uuid, SimulationMeta>,
+    );
+
+    fn fixture() -> Fixture {
+        let simulations = vec![
+            overview(1, "2024-05-01T10:00:00Z"),
+            overview(2, "2024-05-02T09:00:00Z"),
+            overview(3, "2024-05-02T18:00:00Z"),
+        ];
+        let details = HashMap::from([
+            (uuid(1), simulation(300, &["Battery 1"])),
+            (uuid(3), simulation(100, &["Solar Panel"])),
+        ]);
+        let metadata = BTreeMap::from([(
+            uuid(2),
+            SimulationMeta {
+                name: "Baseline".to_string(),
+                tags: vec!["summer".to_string()],
+                notes: String::new(),
+            },
+        )]);
+        (simulations, details, metadata)
+    }
+
+    #[test]
+    fn sorts_by_time_and_result() {
+        let (simulations, details, metadata) = fixture();
+        let mut filter = ListFilter::default();
+        assert_eq!(
+            ids(&filter.apply(&simulations, &details, &metadata, &TimeZone::UTC)),
+            [3, 2, 1]
+        );
+        filter.sort = SortOrder::OldestFirst;
+        assert_eq!(
+            ids(&filter.apply(&simulations, &details, &metadata, &TimeZone::UTC)),
+            [1, 2, 3]
+        );
+        // unknown results go last either way
+        filter.sort = SortOrder::ResultAscending;
+        assert_eq!(
+            ids(&filter.apply(&simulations, &details, &metadata, &TimeZone::UTC)),
+            [3, 1, 2]
+        );
+        filter.sort = SortOrder::ResultDescending;
+        assert_eq!(
+            ids(&filter.apply(&simulations, &details, &metadata, &TimeZone::UTC)),
+            [1, 3, 2]
+        );
+    }
+
+    #[test]
+    fn needs_details_to_search_or_sort_by_result() {
+        let mut filter = ListFilter::default();
+        assert!(!filter.needs_details());
+        filter.query = "  ".to_string();
+        assert!(!filter.needs_details());
+        filter.query = "battery".to_string();
+        assert!(filter.needs_details());
+        filter.query.clear();
+        filter.sort = SortOrder::ResultAscending;
+        assert!(filter.needs_details());
+        filter.sort = SortOrder::OldestFirst;
+        assert!(!filter.needs_details());
+    }
+
+    #[test]
+    fn filters_by_query_and_dates() {
+        let (simulations, details, metadata) = fixture();
+        let filter = ListFilter {
+            query: "solar".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&filter.apply(&simulations, &details, &metadata, &TimeZone::UTC)),
+            [3]
+        );
+
+        let filter = ListFilter {
+            query: "02".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&filter.apply(&simulations, &details, &metadata, &TimeZone::UTC)),
+            [2]
+        );
+
+        let filter = ListFilter {
+            query: "tag:sum".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&filter.apply(&simulations, &details, &metadata, &TimeZone::UTC)),
+            [2]
+        );
+
+        let filter = ListFilter {
+            to: Some("2024-05-01".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&filter.apply(&simulations, &details, &metadata, &TimeZone::UTC)),
+            [1]
+        );
+    }
+
+    #[test]
+    fn groups_by_day() {
+        let (simulations, details, metadata) = fixture();
+        let filter = ListFilter {
+            sort: SortOrder::ResultAscending,
+            group_by_day: true,
+            ..Default::default()
+        };
+        let groups = filter.apply(&simulations, &details, &metadata, &TimeZone::UTC);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, Some("2024-05-02".parse().unwrap()));
+        assert_eq!(ids(&groups), [3, 2, 1]);
+    }
+}
@@ -0,0 +1,247 @@
+//! A simulation followed while it runs.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::api::{Report, RunState, SimulationEvent, SimulationStatus};
+use crate::domain::DeviceName;
+use crate::table::{LogTable, Table};
+
+/// How many of the newest lines and table rows are kept per device.
+pub const MAX_LINES: usize = 5_000;
+
+/// Least time between drawing charts while the run goes on.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What a device logged so far.
+#[derive(Debug, Clone)]
+pub struct DeviceLog {
+    pub device: DeviceName,
+    /// The newest [`MAX_LINES`] lines.
+    pub lines: VecDeque<String>,
+    /// The lines parsed as they arrived.
+    table: LogTable,
+    /// Whether rows arrived since the chart was last drawn.
+    changed: bool,
+}
+
+impl DeviceLog {
+    fn new(device: DeviceName) -> Self {
+        Self {
+            device,
+            lines: VecDeque::new(),
+            table: LogTable::default(),
+            changed: false,
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        if self.table.push_line(&line) {
+            self.changed = true;
+            // trimmed in batches, as dropping the front of the rows moves all others
+            if self.table.table().rows.len() >= MAX_LINES + MAX_LINES / 4 {
+                self.table.keep_last(MAX_LINES);
+            }
+        }
+        if self.lines.len() == MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+}
+
+/// Everything received about a running simulation so far.
+#[derive(Debug, Clone)]
+pub struct LiveRun {
+    pub id: Uuid,
+    pub status: Option<SimulationStatus>,
+    /// Log lines received per device, in order of first appearance.
+    pub logs: Vec<DeviceLog>,
+    /// Set once the run ended, successfully or not.
+    pub outcome: Option<Result<Report, String>>,
+    /// When charts were last drawn.
+    drawn_at: Option<Instant>,
+}
+
+impl LiveRun {
+    pub fn new(id: Uuid) -> Self {
+        Self {
+            id,
+            status: None,
+            logs: Vec::new(),
+            outcome: None,
+            drawn_at: None,
+        }
+    }
+
+    /// Applies `event`, returning whether charts are out of date.
+    ///
+    /// Charts are redrawn once per completed tick rather than per log line, unless the
+    /// server doesn't report ticks.
+    pub fn apply(&mut self, event: SimulationEvent) -> bool {
+        match event {
+            SimulationEvent::Status(status) => {
+                let ticked = self.status.map(|s| s.tick) != Some(status.tick);
+                self.status = Some(status);
+                if status.state == RunState::Failed && self.outcome.is_none() {
+                    self.outcome = Some(Err("Simulation failed".to_string()));
+                }
+                ticked
+            }
+            SimulationEvent::Log(log) => {
+                let index = match self
+                    .logs
+                    .iter()
+                    .position(|logged| logged.device.as_str() == log.device.as_str())
+                {
+                    Some(index) => index,
+                    None => {
+                        self.logs.push(DeviceLog::new(log.device));
+                        self.logs.len() - 1
+                    }
+                };
+                self.logs[index].push(log.line);
+                self.status.is_none()
+            }
+            SimulationEvent::Finished(report) => {
+                self.outcome = Some(Ok(report));
+                true
+            }
+            SimulationEvent::Failed(reason) => {
+                self.outcome = Some(Err(reason));
+                false
+            }
+        }
+    }
+
+    /// Completed share of the run between 0 and 1, if the server reports it.
+    pub fn progress(&self) -> Option<f32> {
+        if matches!(self.outcome, Some(Ok(_))) {
+            return Some(1.0);
+        }
+        let status = self.status?;
+        let total = status.total_ticks.filter(|total| *total > 0)?;
+        Some((status.tick as f32 / total as f32).min(1.0))
+    }
+
+    /// The structured logs of devices whose chart is out of date, which then count as drawn.
+    ///
+    /// Returns nothing if charts were drawn less than [`REDRAW_INTERVAL`] before `now`
+    /// while the run goes on, so fast runs don't redraw every chart on each tick.
+    pub fn charts_to_draw(&mut self, now: Instant) -> Vec<(&DeviceName, &Table)> {
+        let recent = self
+            .drawn_at
+            .is_some_and(|drawn| now.duration_since(drawn) < REDRAW_INTERVAL);
+        if recent && self.outcome.is_none() {
+            return Vec::new();
+        }
+        self.drawn_at = Some(now);
+        self.logs
+            .iter_mut()
+            .filter_map(|log| {
+                let changed = std::mem::take(&mut log.changed);
+                let log: &DeviceLog = log;
+                changed.then(|| (&log.device, log.table.table()))
+            })
+            .collect()
+    }
+
+    /// A one line summary like "Running, tick 3 of 96".
+    pub fn describe(&self) -> String {
+        match (&self.outcome, self.status) {
+            (Some(Ok(report)), _) => format!("Finished with {} Wh", report.result),
+            (Some(Err(reason)), _) => format!("Failed: {reason}"),
+            (None, None) => "Waiting for the server…".to_string(),
+            (None, Some(status)) => match (status.state, status.total_ticks) {
+                (RunState::Queued, _) => "Queued".to_string(),
+                (_, Some(total)) => format!("Running, tick {} of {total}", status.tick),
+                (_, None) => format!("Running, tick {}", status.tick),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::LogLine;
+
+    fn status(tick: u64) -> SimulationEvent {
+        SimulationEvent::Status(SimulationStatus {
+            state: RunState::Running,
+            tick,
+            total_ticks: Some(4),
+        })
+    }
+
+    fn log(device: &str, line: &str) -> SimulationEvent {
+        SimulationEvent::Log(LogLine {
+            device: DeviceName::try_new(device).unwrap(),
+            line: line.to_string(),
+        })
+    }
+
+    #[test]
+    fn redraws_once_per_tick() {
+        let mut run = LiveRun::new(Uuid::nil());
+        assert!(run.apply(log("Battery", "{\"tick\":1,\"charge\":5}")));
+        assert!(run.apply(status(1)));
+        assert!(!run.apply(log("Battery", "{\"tick\":2,\"charge\":7}")));
+        assert!(!run.apply(status(1)));
+        assert!(run.apply(status(2)));
+        assert_eq!(run.progress(), Some(0.5));
+        assert_eq!(run.describe(), "Running, tick 2 of 4");
+
+        let charts = run.charts_to_draw(Instant::now());
+        assert_eq!(charts.len(), 1);
+        assert_eq!(charts[0].1.rows.len(), 2);
+    }
+
+    #[test]
+    fn draws_changed_charts_at_most_every_interval() {
+        let mut run = LiveRun::new(Uuid::nil());
+        let start = Instant::now();
+        run.apply(log("Battery", "{\"tick\":1,\"charge\":5}"));
+        run.apply(log("Solar", "{\"tick\":1,\"power\":2}"));
+        assert_eq!(run.charts_to_draw(start).len(), 2);
+
+        run.apply(log("Solar", "{\"tick\":2,\"power\":3}"));
+        assert!(run.charts_to_draw(start + REDRAW_INTERVAL / 2).is_empty());
+        let charts = run.charts_to_draw(start + REDRAW_INTERVAL);
+        assert_eq!(charts.len(), 1);
+        assert_eq!(charts[0].0.as_str(), "Solar");
+        assert!(run.charts_to_draw(start + REDRAW_INTERVAL * 2).is_empty());
+    }
+
+    #[test]
+    fn keeps_the_newest_lines() {
+        let mut run = LiveRun::new(Uuid::nil());
+        for tick in 0..MAX_LINES * 2 {
+            run.apply(log("Battery", &format!("{{\"tick\":{tick}}}")));
+        }
+        let battery = &run.logs[0];
+        assert_eq!(battery.lines.len(), MAX_LINES);
+        assert_eq!(
+            battery.lines.back().unwrap(),
+            &format!("{{\"tick\":{}}}", MAX_LINES * 2 - 1)
+        );
+        let rows = battery.table.table().rows.len();
+        assert!((MAX_LINES..MAX_LINES + MAX_LINES / 4).contains(&rows));
+    }
+
+    #[test]
+    fn groups_lines_by_device() {
+        let mut run = LiveRun::new(Uuid::nil());
+        run.apply(log("Battery", "a"));
+        run.apply(log("Solar", "b"));
+        run.apply(log("Battery", "c"));
+        let lines = run
+            .logs
+            .iter()
+            .map(|log| log.lines.len())
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [2, 1]);
+    }
+}
@@ -1,14 +1,46 @@
 mod api;
 mod app;
+mod auth;
+mod cache;
+mod cli;
+mod config;
+mod csv;
+mod diff;
 mod domain;
+mod export;
+mod history;
+mod key_binds;
+mod list_filter;
+mod live;
+mod metadata;
+mod notification;
+mod palette;
+mod refresh;
+mod report;
 mod simulation;
+mod sse;
+mod table;
+mod tls;
+mod virtual_list;
 
 fn main() -> cosmic::iced::Result {
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(args) if args.help => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     let settings = cosmic::app::Settings::default().size_limits(
         cosmic::iced::Limits::NONE
             .min_width(360.0)
             .min_height(180.0),
     );
 
-    cosmic::app::run::<app::AppModel>(settings, ())
+    cosmic::app::run::<app::AppModel>(settings, args)
 }
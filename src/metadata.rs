@@ -0,0 +1,76 @@
+//! Names, tags and notes users attach to simulations.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// User supplied information about one simulation, stored locally by id.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
+pub struct SimulationMeta {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub notes: String,
+}
+
+impl SimulationMeta {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty() && self.tags.is_empty() && self.notes.is_empty()
+    }
+
+    /// The name if one was given, the id otherwise.
+    pub fn display_name(meta: Option<&Self>, id: Uuid) -> String {
+        match meta {
+            Some(meta) if !meta.name.is_empty() => meta.name.clone(),
+            _ => id.to_string(),
+        }
+    }
+
+    /// Whether one lowercase search term matches.
+    ///
+    /// `tag:solar` only matches tags starting with `solar`, plain terms match part of
+    /// the name, the notes or a tag.
+    pub fn matches(&self, term: &str) -> bool {
+        if let Some(tag) = term.strip_prefix("tag:") {
+            return self.tags.iter().any(|t| t.to_lowercase().starts_with(tag));
+        }
+        self.name.to_lowercase().contains(term)
+            || self.notes.to_lowercase().contains(term)
+            || self.tags.iter().any(|t| t.to_lowercase().contains(term))
+    }
+}
+
+/// Splits comma separated tags, dropping empty ones and duplicates.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tags_trims_and_dedups() {
+        assert_eq!(
+            parse_tags(" summer, Battery,,summer , battery"),
+            ["summer", "Battery"]
+        );
+    }
+
+    #[test]
+    fn matches_tags_name_and_notes() {
+        let meta = SimulationMeta {
+            name: "Roof upgrade".to_string(),
+            tags: vec!["Solar".to_string()],
+            notes: "with second battery".to_string(),
+        };
+        assert!(meta.matches("roof"));
+        assert!(meta.matches("second"));
+        assert!(meta.matches("tag:sol"));
+        assert!(!meta.matches("tag:roof"));
+    }
+}
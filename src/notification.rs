@@ -0,0 +1,80 @@
+//! Desktop notifications through the freedesktop notification service.
+
+use std::collections::HashMap;
+
+use cosmic::iced::futures::{Stream, StreamExt};
+use zbus::zvariant::Value;
+
+/// Key of the action opening the simulation a notification is about.
+const OPEN_ACTION: &str = "open";
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+/// What happened to a notification, which may be another application's.
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    /// The action opening the result was invoked.
+    Opened(u32),
+    /// Expired, dismissed or closed after an action.
+    Closed(u32),
+}
+
+/// Shows a notification, returning its id.
+///
+/// With `openable` the notification gets an action to open the result, which
+/// [`events`] reports.
+pub async fn notify(app_id: &str, summary: &str, body: &str, openable: bool) -> eyre::Result<u32> {
+    let connection = zbus::Connection::session().await?;
+    let proxy = NotificationsProxy::new(&connection).await?;
+    let actions: &[&str] = if openable {
+        &[OPEN_ACTION, "Open result"]
+    } else {
+        &[]
+    };
+    let hints = HashMap::from([("desktop-entry", Value::from(app_id))]);
+    let id = proxy
+        .notify("ha-ui", 0, app_id, summary, body, actions, hints, -1)
+        .await?;
+    Ok(id)
+}
+
+/// Notifications being opened or closed, from any application.
+pub async fn events() -> eyre::Result<impl Stream<Item = Event>> {
+    let connection = zbus::Connection::session().await?;
+    let proxy = NotificationsProxy::new(&connection).await?;
+    // one stream for both signals, so an action is seen before the close following it
+    let signals = proxy.inner().receive_all_signals().await?;
+    Ok(signals.filter_map(|message| async move {
+        if let Some(signal) = ActionInvoked::from_message(message.clone()) {
+            let args = signal.args().ok()?;
+            return (args.action_key == OPEN_ACTION).then_some(Event::Opened(args.id));
+        }
+        let signal = NotificationClosed::from_message(message)?;
+        let args = signal.args().ok()?;
+        Some(Event::Closed(args.id))
+    }))
+}
@@ -0,0 +1,77 @@
+/// Scores how well `query` fuzzy matches `candidate`, higher is better.
+///
+/// Every character of the query has to appear in the candidate in the same order, ignoring
+/// case. Consecutive matches and matches at the start of a word score higher, so that `os`
+/// ranks "Open Simulation" above "Close".
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut candidate = candidate.chars().enumerate();
+    let mut prev_char: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        loop {
+            let (i, c) = candidate.next()?;
+            let before = prev_char.replace(c);
+            if !c.to_lowercase().eq(q.to_lowercase()) {
+                continue;
+            }
+
+            score += 1;
+            if !before.is_some_and(char::is_alphanumeric) {
+                score += 8;
+            } else if last_match.is_some_and(|j| j + 1 == i) {
+                score += 5;
+            } else {
+                score -= 1;
+            }
+            last_match = Some(i);
+            break;
+        }
+    }
+
+    Some(score)
+}
+
+/// Filters `entries` by `query` and orders them by descending score.
+///
+/// An empty query keeps every entry in its original order.
+pub fn filter<'a, T>(query: &str, entries: impl IntoIterator<Item = (T, &'a str)>) -> Vec<T> {
+    let mut scored = entries
+        .into_iter()
+        .filter_map(|(entry, label)| Some((fuzzy_score(query, label)?, entry)))
+        .collect::<Vec<_>>();
+    // stable sort, equal scores stay in their original order
+    scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+    scored.into_iter().map(|(_, entry)| entry).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_all_chars_in_order() {
+        assert!(fuzzy_score("sve", "Save").is_some());
+        assert!(fuzzy_score("evs", "Save").is_none());
+        assert!(fuzzy_score("saves", "Save").is_none());
+    }
+
+    #[test]
+    fn ignores_case_and_whitespace() {
+        assert!(fuzzy_score("OPEN file", "Open File").is_some());
+    }
+
+    #[test]
+    fn prefers_word_starts_and_runs() {
+        let labels = ["Close", "Open Simulation", "Toggle settings"];
+        let ranked = filter("os", labels.iter().map(|l| (*l, *l)));
+        assert_eq!(ranked[0], "Open Simulation");
+    }
+
+    #[test]
+    fn empty_query_keeps_order() {
+        let labels = ["b", "a", "c"];
+        assert_eq!(filter("", labels.iter().map(|l| (*l, *l))), labels);
+    }
+}
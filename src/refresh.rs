@@ -0,0 +1,87 @@
+//! Polling the server for simulations submitted elsewhere.
+
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::api::SimulationOverview;
+
+/// Choices offered for the polling interval in seconds, 0 turns polling off.
+pub const INTERVALS: [u32; 5] = [0, 10, 30, 60, 300];
+pub const INTERVAL_LABELS: [&str; 5] = ["Off", "10 seconds", "30 seconds", "1 minute", "5 minutes"];
+
+/// Longest wait between polls while the server is unreachable.
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+/// How long to wait before the next poll after `failures` failed polls in a row.
+pub fn interval(base: Duration, failures: u32) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(failures))
+        .min(MAX_BACKOFF.max(base))
+}
+
+/// Merges the newest simulations from the server into `current`, returning added ids.
+///
+/// Simulations missing from `latest` were deleted if they are at least as new as the
+/// oldest one in it, or at all if `latest` is `complete`. Older ones may simply be on a
+/// later page.
+pub fn merge(
+    current: &mut Vec<SimulationOverview>,
+    latest: Vec<SimulationOverview>,
+    complete: bool,
+) -> Vec<Uuid> {
+    let oldest = latest.iter().map(|sim| sim.timestamp).min();
+    current.retain(|sim| {
+        let covered = complete || oldest.is_some_and(|oldest| sim.timestamp >= oldest);
+        !covered || latest.iter().any(|new| new.id == sim.id)
+    });
+
+    let mut added = Vec::new();
+    for sim in latest {
+        if !current.iter().any(|known| known.id == sim.id) {
+            added.push(sim.id);
+            current.push(sim);
+        }
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overview(id: u128, second: i64) -> SimulationOverview {
+        SimulationOverview {
+            id: Uuid::from_u128(id),
+            timestamp: jiff::Timestamp::from_second(second).unwrap(),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_limit() {
+        let base = Duration::from_secs(30);
+        assert_eq!(interval(base, 0), base);
+        assert_eq!(interval(base, 2), Duration::from_secs(120));
+        assert_eq!(interval(base, 40), MAX_BACKOFF);
+        // a base beyond the limit isn't shortened
+        assert_eq!(
+            interval(Duration::from_secs(900), 3),
+            Duration::from_secs(900)
+        );
+    }
+
+    #[test]
+    fn merge_adds_new_and_drops_deleted() {
+        let mut current = vec![overview(1, 10), overview(2, 20), overview(3, 30)];
+        // 3 was deleted, 1 is older than the page and kept
+        let added = merge(&mut current, vec![overview(4, 40), overview(2, 20)], false);
+        assert_eq!(added, [Uuid::from_u128(4)]);
+        let ids = current
+            .iter()
+            .map(|sim| sim.id.as_u128())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [1, 2, 4]);
+
+        merge(&mut current, vec![overview(4, 40)], true);
+        assert_eq!(current.len(), 1);
+    }
+}
@@ -0,0 +1,577 @@
+//! HTML and PDF reports of finished simulations, rendered from a customizable template.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use eyre::WrapErr;
+
+use crate::api;
+use crate::export::SimulationData;
+use crate::simulation::{self, DeviceType};
+use crate::table::Table;
+
+/// The template used unless the user picked their own in the settings.
+///
+/// Placeholders like `{{result}}` are replaced by [`render`], see the template for the
+/// full list.
+pub const DEFAULT_TEMPLATE: &str = include_str!("../res/report_template.html");
+
+/// Converts the HTML report at `{input}` to a PDF at `{output}`.
+pub const DEFAULT_PDF_COMMAND: &str = "wkhtmltopdf {input} {output}";
+
+const CHART_WIDTH: f64 = 640.0;
+const CHART_HEIGHT: f64 = 200.0;
+const CHART_MARGIN: f64 = 48.0;
+const LEGEND_LINE: f64 = 16.0;
+const COLORS: [&str; 6] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b",
+];
+/// Log columns plotted on the x axis when present, otherwise the entry index is used.
+const X_COLUMNS: [&str; 3] = ["tick", "time", "timestamp"];
+
+/// Everything a report is made of.
+#[derive(Debug, Clone)]
+pub struct ReportData {
+    pub data: SimulationData,
+    /// The submitted simulation, as returned by the server.
+    pub source: String,
+}
+
+impl ReportData {
+    pub async fn fetch(
+        client: &api::Client,
+        overview: &api::SimulationOverview,
+    ) -> eyre::Result<Self> {
+        let data = SimulationData::fetch(client, overview.id, Some(overview.timestamp)).await?;
+        let source = client
+            .fetch_simulation_source(overview.id)
+            .await
+            .wrap_err_with(|| format!("Failed fetching source of simulation {}", overview.id))?;
+        Ok(Self { data, source })
+    }
+}
+
+/// Fetches a simulation and writes its report to `path`.
+///
+/// A `path` ending in `.pdf` is rendered to HTML first and converted with `pdf_command`,
+/// falling back to [`DEFAULT_PDF_COMMAND`].
+pub async fn generate(
+    client: &api::Client,
+    overview: &api::SimulationOverview,
+    template: Option<&Path>,
+    pdf_command: Option<&str>,
+    path: &Path,
+) -> eyre::Result<()> {
+    let template = match template {
+        Some(template) => std::fs::read_to_string(template)
+            .wrap_err_with(|| format!("Failed reading template {}", template.display()))?,
+        None => DEFAULT_TEMPLATE.to_string(),
+    };
+    let report = ReportData::fetch(client, overview).await?;
+    let html = render(&template, &report, jiff::Timestamp::now());
+
+    let is_pdf = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+    if !is_pdf {
+        return std::fs::write(path, html)
+            .wrap_err_with(|| format!("Failed writing {}", path.display()));
+    }
+
+    let input = write_temp_html(&html)?;
+    let res = convert_to_pdf(pdf_command.unwrap_or(DEFAULT_PDF_COMMAND), &input, path).await;
+    let _ = std::fs::remove_file(&input);
+    res
+}
+
+/// Writes `html` to a new file in the temporary directory, readable only by the user.
+///
+/// The name is random and the file must not exist yet, so nothing placed there by another
+/// user, like a symlink, is written through.
+fn write_temp_html(html: &str) -> eyre::Result<PathBuf> {
+    use std::io::Write as _;
+    #[cfg(unix)]
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let path = std::env::temp_dir().join(format!("ha-ui-report-{}.html", uuid::Uuid::new_v4()));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options
+        .open(&path)
+        .wrap_err_with(|| format!("Failed creating {}", path.display()))?;
+    if let Err(e) = file.write_all(html.as_bytes()) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e).wrap_err_with(|| format!("Failed writing {}", path.display()));
+    }
+    Ok(path)
+}
+
+async fn convert_to_pdf(command: &str, input: &Path, output: &Path) -> eyre::Result<()> {
+    let args = pdf_command_args(command, input, output);
+    let Some((program, args)) = args.split_first() else {
+        eyre::bail!("No PDF converter configured");
+    };
+    let out = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .wrap_err_with(|| format!("Failed running PDF converter {}", program.display()))?;
+    if !out.status.success() {
+        eyre::bail!(
+            "PDF converter failed ({}): {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Splits `command` at whitespace and fills in the `{input}` and `{output}` paths.
+///
+/// Substitution happens after splitting, so paths containing spaces stay one argument.
+fn pdf_command_args(command: &str, input: &Path, output: &Path) -> Vec<PathBuf> {
+    command
+        .split_whitespace()
+        .map(|arg| match arg {
+            "{input}" => input.to_path_buf(),
+            "{output}" => output.to_path_buf(),
+            arg => PathBuf::from(arg),
+        })
+        .collect()
+}
+
+/// Fills the placeholders of `template` with the contents of `report`.
+///
+/// Unknown placeholders are left untouched so typos are visible in the output.
+pub fn render(template: &str, report: &ReportData, generated: jiff::Timestamp) -> String {
+    let source = serde_json::from_str::<simulation::Simulation>(&report.source).ok();
+    let value = |key: &str| -> Option<String> {
+        Some(match key {
+            "title" => "Simulation report".to_string(),
+            "id" => report.data.id.to_string(),
+            "timestamp" => report
+                .data
+                .timestamp
+                .map(|t| t.to_string())
+                .unwrap_or_default(),
+            "generated" => generated.to_string(),
+            "result" => report.data.simulation.res.result.to_string(),
+            "parameters" => parameters(source.as_ref(), &report.source),
+            "devices" => devices(source.as_ref(), &report.data),
+            "kpis" => kpis(source.as_ref(), &report.data),
+            "charts" => charts(&report.data),
+            _ => return None,
+        })
+    };
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        match value(after[..end].trim()) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn html_table<R, C>(header: &[&str], rows: R) -> String
+where
+    R: IntoIterator<Item = C>,
+    C: IntoIterator<Item = String>,
+{
+    let mut out = String::from("<table>\n<tr>");
+    for column in header {
+        let _ = write!(out, "<th>{}</th>", escape(column));
+    }
+    out.push_str("</tr>\n");
+    for row in rows {
+        out.push_str("<tr>");
+        for cell in row {
+            let class = if cell.parse::<f64>().is_ok() {
+                " class=\"number\""
+            } else {
+                ""
+            };
+            let _ = write!(out, "<td{class}>{}</td>", escape(&cell));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+fn hours(source: &simulation::Simulation) -> f64 {
+    let millis = source.end_time().as_millisecond() - source.start_time().as_millisecond();
+    millis as f64 / 3_600_000.0
+}
+
+fn parameters(source: Option<&simulation::Simulation>, raw: &str) -> String {
+    let Some(source) = source else {
+        return format!("<pre>{}</pre>\n", escape(raw));
+    };
+    html_table(
+        &["Parameter", "Value"],
+        [
+            ["Start".to_string(), source.start_time().to_string()],
+            ["End".to_string(), source.end_time().to_string()],
+            ["Duration (h)".to_string(), format!("{:.2}", hours(source))],
+        ],
+    )
+}
+
+fn devices(source: Option<&simulation::Simulation>, data: &SimulationData) -> String {
+    let Some(source) = source else {
+        return html_table(
+            &["Name"],
+            data.simulation
+                .devices
+                .iter()
+                .map(|device| [device.as_str().to_string()]),
+        );
+    };
+    html_table(
+        &["Name", "Type", "Parameters"],
+        source.devices().iter().map(|device| {
+            let (kind, params) = match device.device_type() {
+                DeviceType::SolarPanel => ("Solar panel", String::new()),
+                DeviceType::StableDevice { produces } => {
+                    ("Stable device", format!("produces {produces} W"))
+                }
+                DeviceType::Store {
+                    max_charge_per_tick,
+                    max_capacity,
+                } => (
+                    "Store",
+                    format!(
+                        "max. charge {max_charge_per_tick} Wh per tick, capacity {max_capacity} Wh"
+                    ),
+                ),
+            };
+            [device.name().as_str().to_string(), kind.to_string(), params]
+        }),
+    )
+}
+
+fn kpis(source: Option<&simulation::Simulation>, data: &SimulationData) -> String {
+    let result = data.simulation.res.result;
+    let mut rows = vec![
+        ["Result (Wh)".to_string(), result.to_string()],
+        [
+            "Devices".to_string(),
+            data.simulation.devices.len().to_string(),
+        ],
+    ];
+
+    if let Some(source) = source {
+        let hours = hours(source);
+        if hours > 0.0 {
+            rows.push([
+                "Average power (W)".to_string(),
+                format!("{:.1}", result as f64 / hours),
+            ]);
+        }
+
+        let (mut solar, mut production, mut consumption, mut storage) = (0, 0i64, 0i64, 0u64);
+        for device in source.devices() {
+            match *device.device_type() {
+                DeviceType::SolarPanel => solar += 1,
+                DeviceType::StableDevice { produces } if produces >= 0 => {
+                    production += i64::from(produces)
+                }
+                DeviceType::StableDevice { produces } => consumption -= i64::from(produces),
+                DeviceType::Store { max_capacity, .. } => storage += u64::from(max_capacity),
+            }
+        }
+        rows.extend([
+            ["Solar panels".to_string(), solar.to_string()],
+            ["Stable production (W)".to_string(), production.to_string()],
+            [
+                "Stable consumption (W)".to_string(),
+                consumption.to_string(),
+            ],
+            ["Storage capacity (Wh)".to_string(), storage.to_string()],
+        ]);
+    }
+
+    html_table(&["Key figure", "Value"], rows)
+}
+
+fn charts(data: &SimulationData) -> String {
+    let mut out = String::new();
+    for (device, log) in &data.logs {
+        let _ = writeln!(
+            out,
+            "<figure>\n<figcaption>{}</figcaption>",
+            escape(device.as_str())
+        );
+        match Table::parse_log(log) {
+            Some(table) => {
+                if let Some(svg) = chart(&table) {
+                    out.push_str(&svg);
+                }
+                out.push_str(&log_statistics(&table));
+            }
+            None if log.trim().is_empty() => out.push_str("<p>No log entries.</p>\n"),
+            None => {
+                let _ = writeln!(out, "<pre>{}</pre>", escape(log));
+            }
+        }
+        out.push_str("</figure>\n");
+    }
+    out
+}
+
+/// The values of column `i`, `None` unless every non-empty cell is a number.
+fn numeric_column(table: &Table, i: usize) -> Option<Vec<Option<f64>>> {
+    let values = table
+        .rows
+        .iter()
+        .map(|row| match row[i].as_str() {
+            "" => Ok(None),
+            cell => cell.parse::<f64>().map(Some),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    values.iter().any(Option::is_some).then_some(values)
+}
+
+/// A named line of a chart, `None` where a log entry has no value.
+type Series<'a> = (&'a str, Vec<Option<f64>>);
+
+/// The x axis and the numeric series of a log.
+fn series(table: &Table) -> (Vec<f64>, Vec<Series<'_>>) {
+    let mut x = None;
+    let mut series = Vec::new();
+    for (i, column) in table.columns.iter().enumerate() {
+        let Some(values) = numeric_column(table, i) else {
+            continue;
+        };
+        if x.is_none()
+            && X_COLUMNS.contains(&column.to_lowercase().as_str())
+            && values.iter().all(Option::is_some)
+        {
+            x = Some(values.into_iter().flatten().collect());
+        } else {
+            series.push((column.as_str(), values));
+        }
+    }
+    let x = x.unwrap_or_else(|| (0..table.rows.len()).map(|i| i as f64).collect());
+    (x, series)
+}
+
+fn bounds(values: impl IntoIterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values
+        .into_iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+    if min > max {
+        (0.0, 1.0)
+    } else if min == max {
+        (min - 1.0, max + 1.0)
+    } else {
+        (min, max)
+    }
+}
+
+/// Draws the numeric columns of a log as lines, `None` if there is nothing to draw.
+pub fn chart(table: &Table) -> Option<String> {
+    let (x, series) = series(table);
+    if series.is_empty() {
+        return None;
+    }
+
+    let (x_min, x_max) = bounds(x.iter().copied());
+    let (y_min, y_max) = bounds(series.iter().flat_map(|(_, v)| v.iter().flatten().copied()));
+    let px = |v: f64| CHART_MARGIN + (v - x_min) / (x_max - x_min) * CHART_WIDTH;
+    let py = |v: f64| CHART_MARGIN / 2.0 + (y_max - v) / (y_max - y_min) * CHART_HEIGHT;
+    let width = CHART_WIDTH + CHART_MARGIN * 2.0;
+    let legend_top = CHART_HEIGHT + CHART_MARGIN * 1.5;
+    let height = legend_top + LEGEND_LINE * series.len() as f64;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {width} {height}\" \
+         width=\"{width}\" height=\"{height}\" font-size=\"11\">\n"
+    );
+    let _ = writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{CHART_WIDTH}\" height=\"{CHART_HEIGHT}\" \
+         fill=\"none\" stroke=\"#ccc\"/>",
+        CHART_MARGIN,
+        CHART_MARGIN / 2.0
+    );
+    let axis_labels = [
+        (CHART_MARGIN - 4.0, py(y_max) + 4.0, "end", y_max),
+        (CHART_MARGIN - 4.0, py(y_min), "end", y_min),
+        (px(x_min), py(y_min) + 14.0, "start", x_min),
+        (px(x_max), py(y_min) + 14.0, "end", x_max),
+    ];
+    for (lx, ly, anchor, value) in axis_labels {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{lx:.1}\" y=\"{ly:.1}\" text-anchor=\"{anchor}\">{value}</text>"
+        );
+    }
+
+    for (i, (name, values)) in series.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let mut points = String::new();
+        for (x, y) in x.iter().zip(values) {
+            if let Some(y) = y {
+                let _ = write!(points, "{:.1},{:.1} ", px(*x), py(*y));
+            }
+        }
+        let _ = writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{color}\" stroke-width=\"1.5\" points=\"{}\"/>",
+            points.trim_end()
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"{CHART_MARGIN}\" y=\"{:.1}\" fill=\"{color}\">{}</text>",
+            legend_top + LEGEND_LINE * i as f64,
+            escape(name)
+        );
+    }
+    svg.push_str("</svg>\n");
+    Some(svg)
+}
+
+/// Minimum, mean, maximum and last value of every numeric series.
+fn log_statistics(table: &Table) -> String {
+    let (_, series) = series(table);
+    let mut rows = vec![[
+        "Entries".to_string(),
+        String::new(),
+        String::new(),
+        String::new(),
+        table.rows.len().to_string(),
+    ]];
+    for (name, values) in series {
+        let values = values.into_iter().flatten().collect::<Vec<_>>();
+        let (min, max) = values
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+                (min.min(*v), max.max(*v))
+            });
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let last = values.last().copied().unwrap_or_default();
+        rows.push([
+            name.to_string(),
+            min.to_string(),
+            format!("{mean:.2}"),
+            max.to_string(),
+            last.to_string(),
+        ]);
+    }
+    html_table(&["Series", "Min", "Mean", "Max", "Last"], rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DeviceName;
+
+    #[test]
+    fn temporary_html_files_are_new_and_private() {
+        let first = write_temp_html("<p>1</p>").unwrap();
+        let second = write_temp_html("<p>2</p>").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "<p>1</p>");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(first).unwrap();
+        std::fs::remove_file(second).unwrap();
+    }
+
+    fn report() -> ReportData {
+        let battery = DeviceName::try_new("Battery <1>").unwrap();
+        ReportData {
+            data: SimulationData {
+                id: uuid::Uuid::nil(),
+                timestamp: None,
+                simulation: api::Simulation {
+                    devices: vec![battery.clone()],
+                    res: api::Report { result: 1200 },
+                },
+                logs: vec![(
+                    battery,
+                    "{\"tick\":1,\"charge\":5}\n{\"tick\":2,\"charge\":7}\n".to_string(),
+                )],
+            },
+            source: include_str!("../res/example_simulation.json").to_string(),
+        }
+    }
+
+    #[test]
+    fn render_fills_placeholders_and_escapes() {
+        let html = render(
+            "<p>{{ result }}</p>{{charts}}{{unknown}}{{",
+            &report(),
+            jiff::Timestamp::UNIX_EPOCH,
+        );
+        assert!(html.starts_with("<p>1200</p><figure>"));
+        assert!(html.contains("Battery &lt;1&gt;"));
+        assert!(html.ends_with("{{unknown}}{{"));
+    }
+
+    #[test]
+    fn default_template_renders_everything() {
+        let html = render(DEFAULT_TEMPLATE, &report(), jiff::Timestamp::UNIX_EPOCH);
+        assert!(!html.contains("{{"));
+        assert!(html.contains("Average power (W)"));
+    }
+
+    #[test]
+    fn chart_plots_against_tick() {
+        let table =
+            Table::parse_log("[{\"tick\":1,\"charge\":5},{\"tick\":3,\"charge\":7}]").unwrap();
+        let svg = chart(&table).unwrap();
+        assert_eq!(svg.matches("<polyline").count(), 1);
+        assert!(svg.contains("points=\"48.0,224.0 688.0,24.0\""));
+        assert!(chart(&Table::parse_log("[{\"note\":\"a\"}]").unwrap()).is_none());
+    }
+
+    #[test]
+    fn pdf_command_keeps_paths_whole() {
+        let args = pdf_command_args(
+            "convert --quiet {input} {output}",
+            Path::new("/tmp/a b.html"),
+            Path::new("out.pdf"),
+        );
+        assert_eq!(
+            args,
+            ["convert", "--quiet", "/tmp/a b.html", "out.pdf"].map(PathBuf::from)
+        );
+    }
+}
@@ -1,9 +1,10 @@
 use jiff::Timestamp;
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::domain::DeviceName;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type", rename_all_fields = "camelCase")]
 pub enum DeviceType {
     SolarPanel,
@@ -16,7 +17,7 @@ pub enum DeviceType {
     },
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Device {
     name: DeviceName,
@@ -24,14 +25,143 @@ pub struct Device {
     device_type: DeviceType,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Simulation {
     start_time: Timestamp,
     end_time: Timestamp,
+    #[serde(deserialize_with = "unique_devices")]
     devices: Vec<Device>,
 }
 
+fn unique_devices<'de, D>(de: D) -> Result<Vec<Device>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let devices = Vec::<Device>::deserialize(de)?;
+    check_unique_names(&devices).map_err(D::Error::custom)?;
+    Ok(devices)
+}
+
+/// Fails if two devices have the same name, ignoring case.
+pub fn check_unique_names(devices: &[Device]) -> eyre::Result<()> {
+    for (i, device) in devices.iter().enumerate() {
+        if let Some(other) = devices[..i]
+            .iter()
+            .find(|other| other.name.eq_ignore_case(&device.name))
+        {
+            eyre::bail!(
+                "device name \"{}\" is used twice, also as \"{}\"",
+                device.name.as_str(),
+                other.name.as_str()
+            );
+        }
+    }
+    Ok(())
+}
+
+impl Device {
+    pub fn name(&self) -> &DeviceName {
+        &self.name
+    }
+
+    pub fn device_type(&self) -> &DeviceType {
+        &self.device_type
+    }
+}
+
+impl Simulation {
+    pub fn start_time(&self) -> Timestamp {
+        self.start_time
+    }
+
+    pub fn end_time(&self) -> Timestamp {
+        self.end_time
+    }
+
+    pub fn devices(&self) -> &[Device] {
+        &self.devices
+    }
+
+    /// Replaces the devices of the simulation, keeping its time window.
+    pub fn with_devices(self, devices: Vec<Device>) -> Self {
+        Self { devices, ..self }
+    }
+}
+
+/// Reads a device list from CSV.
+///
+/// The first record is a header naming the columns `name`, `type`, `produces`,
+/// `maxChargePerTick` and `maxCapacity` in any order, columns a device type doesn't use
+/// may be left empty or omitted.
+pub fn devices_from_csv(input: &str) -> eyre::Result<Vec<Device>> {
+    let mut records = crate::csv::parse(input)?.into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| eyre::eyre!("CSV file is empty"))?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+    };
+    let name_col = column("name").ok_or_else(|| eyre::eyre!("Missing \"name\" column"))?;
+    let type_col = column("type").ok_or_else(|| eyre::eyre!("Missing \"type\" column"))?;
+    let produces_col = column("produces");
+    let charge_col = column("maxChargePerTick");
+    let capacity_col = column("maxCapacity");
+
+    records
+        .enumerate()
+        .map(|(i, record)| {
+            // header is line 1
+            let line = i + 2;
+            let field = |col: Option<usize>, name: &str| {
+                col.and_then(|col| record.get(col))
+                    .map(|f| f.trim())
+                    .filter(|f| !f.is_empty())
+                    .ok_or_else(|| eyre::eyre!("Line {line}: missing {name}"))
+            };
+            let device_type = match field(Some(type_col), "type")? {
+                "SolarPanel" => DeviceType::SolarPanel,
+                "StableDevice" => DeviceType::StableDevice {
+                    produces: parse_number(field(produces_col, "produces")?, "produces", line)?,
+                },
+                "Store" => DeviceType::Store {
+                    max_charge_per_tick: parse_number(
+                        field(charge_col, "maxChargePerTick")?,
+                        "maxChargePerTick",
+                        line,
+                    )?,
+                    max_capacity: parse_number(
+                        field(capacity_col, "maxCapacity")?,
+                        "maxCapacity",
+                        line,
+                    )?,
+                },
+                other => eyre::bail!("Line {line}: unknown device type \"{other}\""),
+            };
+
+            let name = field(Some(name_col), "name")?;
+            let name = DeviceName::try_new(name).map_err(|e| eyre::eyre!("Line {line}: {e}"))?;
+            Ok(Device { name, device_type })
+        })
+        .collect::<eyre::Result<Vec<_>>>()
+        .and_then(|devices| {
+            check_unique_names(&devices)?;
+            Ok(devices)
+        })
+}
+
+fn parse_number<T>(value: &str, name: &str, line: usize) -> eyre::Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| eyre::eyre!("Line {line}: invalid {name} \"{value}\": {e}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -41,4 +171,52 @@ mod tests {
         let _: Simulation =
             serde_json::from_str(include_str!("../res/example_simulation.json")).unwrap();
     }
+
+    #[test]
+    fn devices_from_csv_works() {
+        let devices = devices_from_csv(
+            "name,type,produces,maxChargePerTick,maxCapacity\n\
+             Solar Panel 1,SolarPanel,,,\n\
+             \"Fryer, kitchen\",StableDevice,-200,,\n\
+             Battery 1,Store,,1000,10000\n",
+        )
+        .unwrap();
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[1].name.as_str(), "Fryer, kitchen");
+        assert!(matches!(
+            devices[2].device_type,
+            DeviceType::Store {
+                max_charge_per_tick: 1000,
+                max_capacity: 10000
+            }
+        ));
+    }
+
+    #[test]
+    fn devices_from_csv_reports_line() {
+        let err = devices_from_csv("name,type\nFryer,StableDevice\n").unwrap_err();
+        assert!(err.to_string().contains("Line 2"));
+        let err = devices_from_csv("name,type\nFryer ©,SolarPanel\n").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Line 2: device name contains '©'"));
+    }
+
+    #[test]
+    fn device_names_must_differ_in_more_than_case() {
+        let json = r#"{
+            "startTime": "2024-01-01T00:00:00Z",
+            "endTime": "2024-01-02T00:00:00Z",
+            "devices": [
+                {"name": "Battery", "type": "SolarPanel"},
+                {"name": "Fryer", "type": "SolarPanel"},
+                {"name": "BATTERY", "type": "SolarPanel"}
+            ]
+        }"#;
+        let e = serde_json::from_str::<Simulation>(json).unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("device name \"BATTERY\" is used twice, also as \"Battery\""));
+        assert!(devices_from_csv("name,type\nfryer,SolarPanel\nFryer,SolarPanel\n").is_err());
+    }
 }
@@ -0,0 +1,105 @@
+//! Incremental parsing of server-sent events.
+
+/// One event received from an event stream.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    /// The event type, `message` unless the server named it.
+    pub event: String,
+    /// The data lines of the event, joined with newlines.
+    pub data: String,
+}
+
+/// Turns chunks of an event stream into events.
+///
+/// Chunks may split lines and even UTF-8 sequences anywhere.
+#[derive(Debug, Default)]
+pub struct Parser {
+    buf: Vec<u8>,
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl Parser {
+    /// Feeds the next chunk, returning the events it completed.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Event> {
+        self.buf.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buf.iter().position(|b| *b == b'\n') {
+            let line = String::from_utf8_lossy(&self.buf[..end])
+                .trim_end_matches('\r')
+                .to_string();
+            self.buf.drain(..=end);
+            if let Some(event) = self.line(&line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    fn line(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            // a blank line dispatches the event, if it had data
+            let event = self.event.take();
+            let data = self.data.take()?;
+            return Some(Event {
+                event: event.unwrap_or_else(|| "message".to_string()),
+                data,
+            });
+        }
+
+        let (field, value) = match line.split_once(':') {
+            // lines starting with a colon are comments
+            Some(("", _)) => return None,
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            // `id` and `retry` only matter for reconnecting, which we leave to the caller
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let mut parser = Parser::default();
+        assert!(parser.push(b"event: log\r\ndata: K").is_empty());
+        // "ü" split in the middle
+        assert!(parser.push(b"\xc3").is_empty());
+        let events = parser.push(b"\xbcche\r\n\r\n: keep-alive\n\ndata: a\ndata: b\n\n");
+        assert_eq!(
+            events,
+            [
+                Event {
+                    event: "log".to_string(),
+                    data: "Küche".to_string(),
+                },
+                Event {
+                    event: "message".to_string(),
+                    data: "a\nb".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn events_without_data_are_dropped() {
+        let mut parser = Parser::default();
+        assert!(parser.push(b"event: ping\n\n").is_empty());
+        // the event type doesn't leak into the next event
+        assert_eq!(parser.push(b"data: x\n\n")[0].event, "message");
+    }
+}
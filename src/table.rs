@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+/// Tabular data, used to turn logs and reports into CSV or Markdown.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(columns: Vec<String>) -> Self {
+        Self {
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Parses a simulation log into one row per entry.
+    ///
+    /// Accepts a JSON array of objects, an object wrapping such an array, or one JSON
+    /// object per line. Nested objects are flattened into `parent.child` columns.
+    pub fn parse_log(text: &str) -> Option<Self> {
+        Self::from_log_json(parse_json_or_lines(text)?)
+    }
+
+    /// Like [`Table::parse_log`] for a log already parsed by [`parse_json_or_lines`].
+    pub fn from_log_json(json: Value) -> Option<Self> {
+        let entries = log_entries(json)?;
+        if entries.is_empty() {
+            return None;
+        }
+
+        let mut table = LogTable::default();
+        for entry in &entries {
+            table.push(entry);
+        }
+        Some(table.table)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        crate::csv::write_record(&mut out, &self.columns);
+        for row in &self.rows {
+            crate::csv::write_record(&mut out, row);
+        }
+        out
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let escape = |cell: &str| cell.replace('|', "\\|").replace('\n', "<br>");
+        let line = |cells: &mut dyn Iterator<Item = String>| {
+            let mut out = String::from("|");
+            for cell in cells {
+                out.push(' ');
+                out.push_str(&cell);
+                out.push_str(" |");
+            }
+            out.push('\n');
+            out
+        };
+
+        let mut out = line(&mut self.columns.iter().map(|c| escape(c)));
+        out.push_str(&line(&mut self.columns.iter().map(|_| "---".to_string())));
+        for row in &self.rows {
+            out.push_str(&line(&mut row.iter().map(|c| escape(c))));
+        }
+        out
+    }
+}
+
+/// A [`Table`] built from log entries as they arrive, for logs that keep growing.
+#[derive(Debug, Clone, Default)]
+pub struct LogTable {
+    table: Table,
+    /// Position of every column in `table.columns`.
+    index: HashMap<String, usize>,
+}
+
+impl LogTable {
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    /// Appends the entries of one log line, returning whether it held any.
+    ///
+    /// A line is either one JSON object or anything [`Table::parse_log`] accepts.
+    pub fn push_line(&mut self, line: &str) -> bool {
+        match serde_json::from_str(line) {
+            Ok(Value::Object(entry)) => {
+                self.push(&entry);
+                true
+            }
+            Ok(json) => match log_entries(json) {
+                Some(entries) => {
+                    for entry in &entries {
+                        self.push(entry);
+                    }
+                    !entries.is_empty()
+                }
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+
+    /// Appends one entry as a row, adding the columns it brings to every row.
+    pub fn push(&mut self, entry: &Map<String, Value>) {
+        let mut fields = Vec::new();
+        flatten("", entry, &mut fields);
+
+        let width = self.table.columns.len();
+        for (key, _) in &fields {
+            if !self.index.contains_key(key) {
+                self.index.insert(key.clone(), self.table.columns.len());
+                self.table.columns.push(key.clone());
+            }
+        }
+        let columns = self.table.columns.len();
+        if columns > width {
+            for row in &mut self.table.rows {
+                row.resize(columns, String::new());
+            }
+        }
+
+        let mut row = vec![String::new(); columns];
+        for (key, value) in fields {
+            row[self.index[&key]] = value;
+        }
+        self.table.rows.push(row);
+    }
+
+    /// Drops the oldest rows so at most `rows` remain.
+    pub fn keep_last(&mut self, rows: usize) {
+        let excess = self.table.rows.len().saturating_sub(rows);
+        self.table.rows.drain(..excess);
+    }
+}
+
+/// Parses `text` as JSON, falling back to one JSON value per line.
+pub fn parse_json_or_lines(text: &str) -> Option<Value> {
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).ok())
+        .collect::<Option<Vec<Value>>>()
+        .filter(|lines| !lines.is_empty())
+        .map(Value::Array)
+}
+
+/// Text shown in the output panel, with the JSON and table it could be read as.
+///
+/// Parsed once where it is fetched, as logs can be large.
+#[derive(Debug, Clone)]
+pub struct ParsedText {
+    pub text: String,
+    pub json: Option<Value>,
+    pub table: Option<Table>,
+}
+
+impl ParsedText {
+    pub fn parse(text: String) -> Self {
+        let json = parse_json_or_lines(&text);
+        let table = json.clone().and_then(Table::from_log_json);
+        Self { text, json, table }
+    }
+}
+
+fn log_entries(json: Value) -> Option<Vec<Map<String, Value>>> {
+    let entries = match json {
+        Value::Array(entries) => entries,
+        // a single wrapper object like `{"entries": [...]}`
+        Value::Object(obj) => obj.into_iter().find_map(|(_, value)| match value {
+            Value::Array(entries) => Some(entries),
+            _ => None,
+        })?,
+        _ => return None,
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            Value::Object(obj) => Some(obj),
+            _ => None,
+        })
+        .collect()
+}
+
+fn flatten(prefix: &str, obj: &Map<String, Value>, out: &mut Vec<(String, String)>) {
+    for (key, value) in obj {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            Value::Object(obj) => flatten(&key, obj, out),
+            value => out.push((key, cell(value))),
+        }
+    }
+}
+
+/// Formats a JSON value for a table cell, without quotes around strings.
+pub fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_lines_and_arrays_alike() {
+        let lines = "{\"tick\":1,\"charge\":5}\n{\"tick\":2,\"charge\":7}\n";
+        let array = "[{\"tick\":1,\"charge\":5},{\"tick\":2,\"charge\":7}]";
+        assert_eq!(Table::parse_log(lines), Table::parse_log(array));
+        assert_eq!(Table::parse_log(lines).unwrap().rows.len(), 2);
+
+        let parsed = ParsedText::parse(lines.to_string());
+        assert_eq!(parsed.table, Table::parse_log(array));
+        assert_eq!(parsed.json, parse_json_or_lines(array));
+    }
+
+    #[test]
+    fn flattens_nested_and_sparse_entries() {
+        let table =
+            Table::parse_log("[{\"tick\":1,\"dev\":{\"w\":3}},{\"tick\":2,\"note\":\"a\"}]")
+                .unwrap();
+        assert_eq!(table.columns, ["dev.w", "tick", "note"]);
+        assert_eq!(table.rows[1], ["", "2", "a"]);
+    }
+
+    #[test]
+    fn builds_log_tables_line_by_line() {
+        let mut table = LogTable::default();
+        assert!(table.push_line("{\"tick\":1,\"dev\":{\"w\":3}}"));
+        assert!(!table.push_line("tick 2"));
+        assert!(table.push_line("{\"tick\":2,\"note\":\"a\"}"));
+        assert_eq!(
+            Some(table.table()),
+            Table::parse_log("[{\"tick\":1,\"dev\":{\"w\":3}},{\"tick\":2,\"note\":\"a\"}]")
+                .as_ref()
+        );
+
+        table.keep_last(1);
+        assert_eq!(table.table().rows, [["", "2", "a"]]);
+    }
+
+    #[test]
+    fn rejects_plain_text() {
+        assert!(Table::parse_log("tick 1: 200 Wh").is_none());
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        let mut table = Table::new(vec!["a".into()]);
+        table.rows.push(vec!["x|y".into()]);
+        assert_eq!(table.to_markdown(), "| a |\n| --- |\n| x\\|y |\n");
+    }
+}
@@ -0,0 +1,163 @@
+//! TLS settings for servers with private certificate authorities or client certificates.
+
+use std::path::{Path, PathBuf};
+
+use eyre::WrapErr;
+use reqwest::{Certificate, ClientBuilder, Identity};
+use serde::{Deserialize, Serialize};
+
+/// TLS settings of a server profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsOptions {
+    /// PEM file with certificate authorities to trust besides the system's.
+    pub ca_bundle: Option<PathBuf>,
+    /// Client certificate, either PEM or PKCS#12 (`.p12`, `.pfx`) with the key included.
+    pub client_certificate: Option<PathBuf>,
+    /// Key of a PEM client certificate, if it isn't in the certificate file.
+    pub client_key: Option<PathBuf>,
+    /// Skips verifying the server's certificate, only ever for development servers.
+    pub accept_invalid_certs: bool,
+}
+
+impl TlsOptions {
+    /// Whether the client certificate is a PKCS#12 archive, which has a password.
+    pub fn is_pkcs12(&self) -> bool {
+        self.client_certificate
+            .as_deref()
+            .and_then(Path::extension)
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("p12") || ext.eq_ignore_ascii_case("pfx"))
+    }
+}
+
+/// Certificates loaded from the files of [`TlsOptions`].
+#[derive(Clone, Default)]
+pub struct TlsConfig {
+    ca_certificates: Vec<Certificate>,
+    identity: Option<Identity>,
+    /// PKCS#12 identities need the native TLS backend, rustls is used otherwise.
+    #[cfg(feature = "pkcs12")]
+    native: bool,
+    accept_invalid_certs: bool,
+}
+
+/// Loads the files `options` refer to.
+///
+/// A PKCS#12 client certificate is left out while its `password` is unknown, an empty
+/// password is used for archives without one.
+pub fn load(options: &TlsOptions, password: Option<&str>) -> eyre::Result<TlsConfig> {
+    let mut config = TlsConfig {
+        accept_invalid_certs: options.accept_invalid_certs,
+        ..TlsConfig::default()
+    };
+    if let Some(path) = &options.ca_bundle {
+        config.ca_certificates = Certificate::from_pem_bundle(&read(path)?)
+            .wrap_err_with(|| format!("No certificates in {}", path.display()))?;
+    }
+    let Some(path) = &options.client_certificate else {
+        return Ok(config);
+    };
+    if options.is_pkcs12() {
+        let Some(password) = password else {
+            return Ok(config);
+        };
+        config.identity = Some(pkcs12_identity(path, &read(path)?, password)?);
+        #[cfg(feature = "pkcs12")]
+        {
+            config.native = true;
+        }
+    } else {
+        let mut pem = read(path)?;
+        if let Some(key) = &options.client_key {
+            pem.push(b'\n');
+            pem.extend(read(key)?);
+        }
+        config.identity = Some(
+            Identity::from_pem(&pem)
+                .wrap_err_with(|| format!("No certificate and key in {}", path.display()))?,
+        );
+    }
+    Ok(config)
+}
+
+#[cfg(feature = "pkcs12")]
+fn pkcs12_identity(path: &Path, der: &[u8], password: &str) -> eyre::Result<Identity> {
+    Identity::from_pkcs12_der(der, password)
+        .wrap_err_with(|| format!("Can't open {}, is the password right?", path.display()))
+}
+
+/// Without the native TLS backend PKCS#12 archives can't be used, only PEM files.
+#[cfg(not(feature = "pkcs12"))]
+fn pkcs12_identity(path: &Path, _der: &[u8], _password: &str) -> eyre::Result<Identity> {
+    eyre::bail!(
+        "Can't use {}, PKCS#12 certificates need ha-ui built with the \"pkcs12\" feature, \
+         convert it to PEM instead",
+        path.display()
+    )
+}
+
+fn read(path: &Path) -> eyre::Result<Vec<u8>> {
+    std::fs::read(path).wrap_err_with(|| format!("Failed reading {}", path.display()))
+}
+
+impl TlsConfig {
+    pub fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        #[cfg(feature = "pkcs12")]
+        let mut builder = if self.native {
+            builder.use_native_tls()
+        } else {
+            builder.use_rustls_tls()
+        };
+        #[cfg(not(feature = "pkcs12"))]
+        let mut builder = builder.use_rustls_tls();
+        for certificate in &self.ca_certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+        if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.clone());
+        }
+        builder.danger_accept_invalid_certs(self.accept_invalid_certs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_pkcs12_by_extension() {
+        let options = |path: &str| TlsOptions {
+            client_certificate: Some(PathBuf::from(path)),
+            ..TlsOptions::default()
+        };
+        assert!(options("/certs/me.P12").is_pkcs12());
+        assert!(options("me.pfx").is_pkcs12());
+        assert!(!options("me.pem").is_pkcs12());
+        assert!(!TlsOptions::default().is_pkcs12());
+    }
+
+    #[test]
+    fn pkcs12_waits_for_password_and_missing_files_fail() {
+        let pkcs12 = TlsOptions {
+            client_certificate: Some(PathBuf::from("/nonexistent/me.p12")),
+            ..TlsOptions::default()
+        };
+        assert!(load(&pkcs12, None).unwrap().identity.is_none());
+        let e = load(&pkcs12, Some("")).err().unwrap();
+        assert_eq!(e.to_string(), "Failed reading /nonexistent/me.p12");
+    }
+
+    #[cfg(not(feature = "pkcs12"))]
+    #[test]
+    fn pkcs12_needs_the_feature() {
+        let path = std::env::temp_dir().join(format!("ha-ui-{}.p12", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"not an archive").unwrap();
+        let pkcs12 = TlsOptions {
+            client_certificate: Some(path.clone()),
+            ..TlsOptions::default()
+        };
+        let e = load(&pkcs12, Some("")).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(e.to_string().contains("\"pkcs12\" feature"));
+    }
+}
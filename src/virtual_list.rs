@@ -0,0 +1,50 @@
+//! Building only the visible rows of long lists.
+
+use std::ops::Range;
+
+/// Height of every row, the list relies on rows not growing with their content.
+pub const ROW_HEIGHT: f32 = 56.0;
+/// Rows built beyond the viewport so fast scrolling doesn't show gaps.
+const OVERSCAN: usize = 4;
+/// How many rows before the end the next page is requested.
+const LOAD_MORE_ROWS: usize = 10;
+
+/// The rows to build for a viewport `height` pixels tall, scrolled `offset` pixels down.
+pub fn visible_range(offset: f32, height: f32, len: usize) -> Range<usize> {
+    let first = (offset.max(0.0) / ROW_HEIGHT) as usize;
+    let count = (height.max(0.0) / ROW_HEIGHT).ceil() as usize + 1;
+    let start = first.saturating_sub(OVERSCAN).min(len);
+    let end = (first + count + OVERSCAN).min(len);
+    start..end
+}
+
+/// Whether the viewport is close enough to the end of the list to load more rows.
+pub fn near_end(offset: f32, height: f32, len: usize) -> bool {
+    visible_range(offset, height, len).end + LOAD_MORE_ROWS >= len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_covers_viewport_and_overscan() {
+        assert_eq!(visible_range(0.0, ROW_HEIGHT * 3.0, 1000), 0..8);
+        assert_eq!(
+            visible_range(ROW_HEIGHT * 100.5, ROW_HEIGHT * 3.0, 1000),
+            96..108
+        );
+        assert_eq!(
+            visible_range(ROW_HEIGHT * 100.0, ROW_HEIGHT * 3.0, 102),
+            96..102
+        );
+        assert_eq!(visible_range(0.0, 600.0, 0), 0..0);
+    }
+
+    #[test]
+    fn near_end_only_at_the_bottom() {
+        assert!(!near_end(0.0, ROW_HEIGHT * 3.0, 1000));
+        assert!(near_end(ROW_HEIGHT * 990.0, ROW_HEIGHT * 3.0, 1000));
+        assert!(near_end(0.0, ROW_HEIGHT * 3.0, 12));
+    }
+}